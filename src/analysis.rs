@@ -0,0 +1,471 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+};
+
+use anyhow::anyhow;
+
+use chrono::{DateTime, Utc};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::config::{RegressionConfig, RegressionRule};
+use crate::locale;
+use crate::plot::{short_sha, PlotKind, Plots, Point};
+
+// Report dates default to `DateTime`'s own format, e.g. `2024-01-31 12:00:00 UTC`, ending in the
+// offset instead for other zones
+fn date(date: &DateTime<Utc>) -> String {
+    locale::current().date(date, "%Y-%m-%d %H:%M:%S %Z")
+}
+
+fn value(value: f64) -> String {
+    locale::current().fixed(value, 2)
+}
+
+// A params line that starts after, or stops before, the rest of its group. This usually means the
+// benchmark definition changed, and explains lines that begin or end partway through a chart.
+#[derive(Debug)]
+pub struct ParamSetChange {
+    pub group: String,
+    pub params: String,
+    pub change: ParamChange,
+}
+
+#[derive(Debug)]
+pub enum ParamChange {
+    // First seen at this commit date, after the group's earliest commit
+    Added(DateTime<Utc>),
+    // Last seen at this commit date, before the group's latest commit
+    Removed(DateTime<Utc>),
+}
+
+impl fmt::Display for ParamSetChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.change {
+            ParamChange::Added(added) => {
+                write!(
+                    f,
+                    "{} `{}`: first appears at {}",
+                    self.group,
+                    self.params,
+                    date(&added)
+                )
+            }
+            ParamChange::Removed(removed) => {
+                write!(
+                    f,
+                    "{} `{}`: last seen at {}",
+                    self.group,
+                    self.params,
+                    date(&removed)
+                )
+            }
+        }
+    }
+}
+
+// Detects params that are present in early but not recent data of a group, or vice versa
+pub fn param_set_changes(plots: &Plots) -> Vec<ParamSetChange> {
+    let mut changes = vec![];
+    for (group, plot) in plots.0.iter() {
+        let firsts = plot.lines.values().filter_map(|points| points.first());
+        let lasts = plot.lines.values().filter_map(|points| points.last());
+        let (Some(group_start), Some(group_end)) =
+            (firsts.map(|p| p.x).min(), lasts.map(|p| p.x).max())
+        else {
+            continue;
+        };
+
+        for (params, points) in plot.lines.iter() {
+            let (Some(first), Some(last)) = (points.first(), points.last()) else {
+                continue;
+            };
+            if first.x > group_start {
+                changes.push(ParamSetChange {
+                    group: group.to_owned(),
+                    params: params.to_owned(),
+                    change: ParamChange::Added(first.x),
+                });
+            }
+            if last.x < group_end {
+                changes.push(ParamSetChange {
+                    group: group.to_owned(),
+                    params: params.to_owned(),
+                    change: ParamChange::Removed(last.x),
+                });
+            }
+        }
+    }
+    changes.sort_by(|a, b| (&a.group, &a.params).cmp(&(&b.group, &b.params)));
+    changes
+}
+
+// A line whose commit dates can't be trusted to reflect commit order, e.g. due to clock skew
+// between runners or a rebased commit
+#[derive(Debug)]
+pub struct TimestampAnomaly {
+    pub group: String,
+    pub params: String,
+    pub kind: AnomalyKind,
+}
+
+#[derive(Debug)]
+pub enum AnomalyKind {
+    // The same commit was stored with two different dates
+    ReusedSha {
+        sha: String,
+        dates: (DateTime<Utc>, DateTime<Utc>),
+    },
+    // `newer` descends from `older` in Git history, yet is dated before it
+    OutOfOrder {
+        older: (String, DateTime<Utc>),
+        newer: (String, DateTime<Utc>),
+    },
+}
+
+impl fmt::Display for TimestampAnomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} `{}`: ", self.group, self.params)?;
+        match &self.kind {
+            AnomalyKind::ReusedSha { sha, dates } => {
+                write!(
+                    f,
+                    "commit {} is dated both {} and {}",
+                    sha,
+                    date(&dates.0),
+                    date(&dates.1)
+                )
+            }
+            AnomalyKind::OutOfOrder { older, newer } => write!(
+                f,
+                "commit {} ({}) is dated before its ancestor {} ({})",
+                newer.0,
+                date(&newer.1),
+                older.0,
+                date(&older.1)
+            ),
+        }
+    }
+}
+
+// Flags lines where the order of points by date disagrees with the order of their commits.
+// `history` is the repository's commits oldest first, as from `git::commit_history`; without it,
+// or for commits outside it, only SHAs stored with conflicting dates are detected
+pub fn timestamp_anomalies(plots: &Plots, history: Option<&[String]>) -> Vec<TimestampAnomaly> {
+    // Points store short SHAs, so look up commits by their 7-char prefix
+    let position = history
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(i, sha)| (short_sha(sha), i))
+        .collect::<HashMap<_, _>>();
+
+    let mut anomalies = vec![];
+    for (group, plot) in plots.0.iter() {
+        for (params, points) in plot.lines.iter() {
+            let mut anomaly = |kind| {
+                anomalies.push(TimestampAnomaly {
+                    group: group.to_owned(),
+                    params: params.to_owned(),
+                    kind,
+                })
+            };
+
+            let mut dates_by_sha = HashMap::new();
+            // The latest commit in Git order seen so far, among points sorted by date
+            let mut latest: Option<(usize, &str, DateTime<Utc>)> = None;
+            for point in points {
+                let Some(sha) = point.sha.as_deref() else {
+                    continue;
+                };
+                if let Some(date) = dates_by_sha.insert(sha, point.x) {
+                    if date != point.x {
+                        anomaly(AnomalyKind::ReusedSha {
+                            sha: sha.to_owned(),
+                            dates: (date, point.x),
+                        });
+                    }
+                }
+
+                let Some(&pos) = position.get(short_sha(sha)) else {
+                    continue;
+                };
+                match latest {
+                    Some((latest_pos, latest_sha, latest_date)) if pos < latest_pos => {
+                        anomaly(AnomalyKind::OutOfOrder {
+                            older: (sha.to_owned(), point.x),
+                            newer: (latest_sha.to_owned(), latest_date),
+                        })
+                    }
+                    _ => latest = Some((pos, sha, point.x)),
+                }
+            }
+        }
+    }
+    anomalies.sort_by(|a, b| (&a.group, &a.params).cmp(&(&b.group, &b.params)));
+    anomalies
+}
+
+// A line whose latest result is worse than the previous commit's, per `RegressionConfig`: slower,
+// or for throughput lower
+#[derive(Debug)]
+pub struct Regression<'a> {
+    pub group: &'a str,
+    pub params: &'a str,
+    pub previous: &'a Point,
+    pub latest: &'a Point,
+    // Whether the verdict compared confidence intervals, rather than only the estimates
+    pub interval_based: bool,
+    // Whether the line's values regressed by falling, as throughput does
+    pub higher_is_better: bool,
+}
+
+impl Regression<'_> {
+    // Change from the previous value as a percentage, e.g. `7.5` for 7.5% slower, or `-7.5` for
+    // 7.5% less throughput
+    pub fn percent_change(&self) -> f64 {
+        (self.latest.y / self.previous.y - 1.0) * 100.0
+    }
+}
+
+impl fmt::Display for Regression<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let change = self.percent_change();
+        write!(
+            f,
+            "{} `{}`: {} -> {} ({}%) at {}",
+            self.group,
+            self.params,
+            value(self.previous.y),
+            value(self.latest.y),
+            locale::current().number(&format!("{:+.1}", change)),
+            date(&self.latest.x)
+        )?;
+        if let (true, Some(prev), Some(cur)) = (
+            self.interval_based,
+            self.previous.interval,
+            self.latest.interval,
+        ) {
+            write!(
+                f,
+                ", interval [{}, {}] is {} [{}, {}]",
+                value(cur.lower),
+                value(cur.upper),
+                if self.higher_is_better {
+                    "below"
+                } else {
+                    "above"
+                },
+                value(prev.lower),
+                value(prev.upper)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Written to `regressions.json` for CI to consume, e.g.
+// `{"group":"Fibonacci","params":"rc=100","old":2.01,"new":2.42,"percent_delta":20.4}`
+impl Serialize for Regression<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Regression", 5)?;
+        s.serialize_field("group", self.group)?;
+        s.serialize_field("params", self.params)?;
+        s.serialize_field("old", &self.previous.y)?;
+        s.serialize_field("new", &self.latest.y)?;
+        s.serialize_field("percent_delta", &self.percent_change())?;
+        s.end()
+    }
+}
+
+// Direction of a change between two consecutive points that is beyond its threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepChange {
+    Regression,
+    Improvement,
+}
+
+// Compares `latest` to `previous` with the configured rule, symmetrically for slowdowns and
+// speedups. For kinds where higher is better, i.e. throughput, a fall is the regression
+pub fn step_change(
+    previous: &Point,
+    latest: &Point,
+    config: &RegressionConfig,
+    kind: PlotKind,
+) -> Option<StepChange> {
+    let improvement = config.improvement_threshold.unwrap_or(config.threshold);
+    let (raised, lowered) = if kind.higher_is_better() {
+        (1.0 + improvement, 1.0 - config.threshold)
+    } else {
+        (1.0 + config.threshold, 1.0 - improvement)
+    };
+    let (rose, fell) = match (config.rule, previous.interval.zip(latest.interval)) {
+        // Disjoint intervals only rule out noise, so the estimate must still move by the threshold
+        (RegressionRule::Disjoint, Some((prev, cur))) => (
+            cur.lower > prev.upper && latest.y > previous.y * raised,
+            cur.upper < prev.lower && latest.y < previous.y * lowered,
+        ),
+        (RegressionRule::Strict, Some((prev, cur))) => (
+            cur.lower > prev.upper * raised,
+            cur.upper < prev.lower * lowered,
+        ),
+        _ => (
+            latest.y > previous.y * raised,
+            latest.y < previous.y * lowered,
+        ),
+    };
+    let (regressed, improved) = if kind.higher_is_better() {
+        (fell, rose)
+    } else {
+        (rose, fell)
+    };
+    if regressed {
+        Some(StepChange::Regression)
+    } else if improved {
+        Some(StepChange::Improvement)
+    } else {
+        None
+    }
+}
+
+// Compares the last two points of every line
+pub fn regressions<'a>(plots: &'a Plots, config: &RegressionConfig) -> Vec<Regression<'a>> {
+    let mut regressions = vec![];
+    for (group, plot) in plots.0.iter() {
+        for (params, points) in plot.lines.iter() {
+            let [.., previous, latest] = points.as_slice() else {
+                continue;
+            };
+            if step_change(previous, latest, config, plot.kind) == Some(StepChange::Regression) {
+                let intervals = previous.interval.zip(latest.interval);
+                regressions.push(Regression {
+                    group,
+                    params,
+                    previous,
+                    latest,
+                    interval_based: config.rule != RegressionRule::Estimate && intervals.is_some(),
+                    higher_is_better: plot.kind.higher_is_better(),
+                });
+            }
+        }
+    }
+    regressions.sort_by_key(|r| (r.group, r.params));
+    regressions
+}
+
+// How much a line's values vary across its history, relative to their size
+#[derive(Debug)]
+pub struct LineStability<'a> {
+    pub group: &'a str,
+    pub params: &'a str,
+    // Coefficient of variation: the standard deviation of the values over their mean
+    pub cv: f64,
+    pub points: usize,
+}
+
+impl fmt::Display for LineStability<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} `{}`: CV {}% over {} points",
+            self.group,
+            self.params,
+            value(self.cv * 100.0),
+            self.points
+        )
+    }
+}
+
+// Ranks every line with at least two points by its coefficient of variation, noisiest first
+pub fn stability_ranking(plots: &Plots) -> Vec<LineStability> {
+    let mut ranking = vec![];
+    for (group, plot) in plots.0.iter() {
+        for (params, points) in plot.lines.iter() {
+            if points.len() < 2 {
+                continue;
+            }
+            let n = points.len() as f64;
+            let mean = points.iter().map(|p| p.y).sum::<f64>() / n;
+            if mean == 0.0 {
+                continue;
+            }
+            let variance = points.iter().map(|p| (p.y - mean).powi(2)).sum::<f64>() / n;
+            ranking.push(LineStability {
+                group,
+                params,
+                cv: variance.sqrt() / mean.abs(),
+                points: points.len(),
+            });
+        }
+    }
+    ranking.sort_by(|a, b| {
+        b.cv.total_cmp(&a.cv)
+            .then_with(|| (a.group, a.params).cmp(&(b.group, b.params)))
+    });
+    ranking
+}
+
+// A line's results at the two commits given to `--compare`
+#[derive(Debug)]
+pub struct Comparison<'a> {
+    pub group: &'a str,
+    pub params: &'a str,
+    pub base: &'a Point,
+    pub head: &'a Point,
+}
+
+impl Comparison<'_> {
+    // Change from the base commit's value to the head's as a percentage
+    pub fn percent_change(&self) -> f64 {
+        (self.head.y / self.base.y - 1.0) * 100.0
+    }
+}
+
+// Pairs up each line's points at the `base` and `head` commits, given as full or abbreviated SHAs.
+// Lines without a result at both are left out, and a commit benchmarked more than once is
+// represented by its latest result. Errors if either SHA matches more than one stored commit
+pub fn compare<'a>(
+    plots: &'a Plots,
+    base: &str,
+    head: &str,
+) -> anyhow::Result<Vec<Comparison<'a>>> {
+    // Points store short SHAs, so the given one only has to agree up to the stored length
+    let matches = |stored: &str, sha: &str| stored.starts_with(&sha[..sha.len().min(stored.len())]);
+    for sha in [base, head] {
+        let commits = plots
+            .0
+            .values()
+            .flat_map(|plot| plot.lines.values().flatten())
+            .filter_map(|p| p.sha.as_deref())
+            .filter(|stored| matches(stored, sha))
+            .collect::<BTreeSet<_>>();
+        if commits.len() > 1 {
+            return Err(anyhow!(
+                "`{}` matches more than one commit: {}",
+                sha,
+                commits.into_iter().collect::<Vec<_>>().join(", ")
+            ));
+        }
+    }
+    let at = |points: &'a [Point], sha: &str| {
+        points.iter().rev().find(|p| {
+            p.sha
+                .as_deref()
+                .map_or(false, |stored| matches(stored, sha))
+        })
+    };
+    let mut comparisons = vec![];
+    for (group, plot) in plots.0.iter() {
+        for (params, points) in plot.lines.iter() {
+            if let (Some(base), Some(head)) = (at(points, base), at(points, head)) {
+                comparisons.push(Comparison {
+                    group,
+                    params,
+                    base,
+                    head,
+                });
+            }
+        }
+    }
+    Ok(comparisons)
+}
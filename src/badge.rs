@@ -0,0 +1,89 @@
+use crate::analysis::{step_change, StepChange};
+use crate::config::RegressionConfig;
+use crate::locale;
+use crate::plot::Plot;
+
+// Background colors of the value half of the badge, as on shields.io
+const REGRESSED: &str = "#e05d44";
+const IMPROVED: &str = "#4c1";
+const UNCHANGED: &str = "#007ec6";
+
+// A shields.io-style SVG badge labeled `label` showing the latest value of the `params` line, e.g.
+// `Fibonacci-num=10 rc=100 | 2.01 ms`. The value is red if it regressed from the previous commit
+// and green if it improved, by the configured regression rule, or blue otherwise. `None` if the
+// plot has no such line
+pub fn badge_svg(
+    label: &str,
+    plot: &Plot,
+    params: &str,
+    regression: &RegressionConfig,
+) -> Option<String> {
+    let points = plot.lines.get(params)?;
+    let latest = points.last()?;
+    let value = match plot.unit_for(latest.y) {
+        Some((unit, divisor)) => format!(
+            "{} {}",
+            locale::current().fixed(latest.y / divisor, 2),
+            unit
+        ),
+        None => locale::current().fixed(latest.y, 0),
+    };
+    let previous = points.len().checked_sub(2).map(|i| &points[i]);
+    let color =
+        match previous.and_then(|previous| step_change(previous, latest, regression, plot.kind)) {
+            Some(StepChange::Regression) => REGRESSED,
+            Some(StepChange::Improvement) => IMPROVED,
+            None => UNCHANGED,
+        };
+
+    let (label_w, value_w) = (text_width(label), text_width(&value));
+    let width = label_w + value_w;
+    let (label, value) = (escape(label), escape(&value));
+    let text = |x: u32, s: &str| {
+        format!(
+            r##"<text x="{x}" y="15" fill="#010101" fill-opacity=".3">{s}</text><text x="{x}" y="14">{s}</text>"##
+        )
+    };
+    Some(format!(
+        concat!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {value}">"##,
+            r##"<title>{label}: {value}</title>"##,
+            r##"<linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>"##,
+            r##"<clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>"##,
+            r##"<g clip-path="url(#r)"><rect width="{label_w}" height="20" fill="#555"/><rect x="{label_w}" width="{value_w}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g>"##,
+            r##"<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">{label_text}{value_text}</g>"##,
+            "</svg>\n"
+        ),
+        width = width,
+        label = label,
+        value = value,
+        label_w = label_w,
+        value_w = value_w,
+        color = color,
+        label_text = text(label_w / 2, &label),
+        value_text = text(label_w + value_w / 2, &value),
+    ))
+}
+
+// Width of a badge half holding `s`, padded on either side. Roughly the advance of 11px Verdana,
+// whose glyphs average about 7px, since the SVG is sized before any font is available
+fn text_width(s: &str) -> u32 {
+    let advance = s
+        .chars()
+        .map(|c| match c {
+            'i' | 'j' | 'l' | '.' | ',' | ':' | ';' | '!' | '|' | '\'' | ' ' => 4,
+            'm' | 'w' | 'M' | 'W' | '%' => 10,
+            c if c.is_uppercase() => 8,
+            _ => 7,
+        })
+        .sum::<u32>();
+    advance + 10
+}
+
+// Escapes the characters with special meaning in SVG text and attributes
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,746 @@
+use std::num::{NonZeroU32, NonZeroUsize};
+
+use camino::Utf8PathBuf;
+
+use chrono::NaiveDate;
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+
+use crate::config::{
+    parse_baseline, parse_group_alias, parse_line_color, Baseline, CaptionAlign, CaptionOverflow,
+    Config, ConflictPolicy, GeoMean, LineColor, MaxAge, Metric, OutputFormat, Quality,
+    RegressionRule, Smoothing, SweepAt, Theme, XAxis,
+};
+#[cfg(feature = "http")]
+use crate::github::PullRequest;
+use crate::locale::Timezone;
+use crate::run::RunOptions;
+
+// Command-line flags. Each one is optional and, when given, overrides the config file.
+#[derive(Debug, Parser)]
+#[command(about = "Plots Criterion benchmark results over Git history")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Config file to read, or to write with `init`. Defaults to `ci-workflows.toml`, which is skipped if it doesn't
+    /// exist. Flags override its values, which override the defaults
+    #[arg(long, value_name = "PATH", global = true)]
+    pub config: Option<Utf8PathBuf>,
+
+    /// Log more detail to stderr: `-v` for progress, `-vv` for each file read and stage timings, `-vvv` for everything.
+    /// By default only warnings are logged, and the paths of written files printed
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Write plot data and render charts even if no points were added or changed
+    #[arg(long)]
+    force: bool,
+
+    /// Read the bench files and check for regressions as usual, but only print a summary instead of writing the plot
+    /// data, charts or any other file
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Only check that the bench files are in the expected format, printing how many records of each matched and were
+    /// rejected. Fails if a file's reject rate is above `--max-reject-rate`
+    #[arg(long)]
+    validate: bool,
+
+    /// Fraction of a file's records that `--validate` allows to be rejected, e.g. `0.05`
+    #[arg(
+        long,
+        value_name = "RATE",
+        default_value_t = 0.0,
+        requires = "validate"
+    )]
+    max_reject_rate: f64,
+
+    /// Commit whose bench files are added by default and whose short SHA fills `{sha}` in `output_stem`. Defaults to
+    /// `HEAD` with `--stamp-head`, and otherwise the commit the plotter was built at
+    #[arg(long, value_name = "SHA", value_parser = parse_sha)]
+    sha: Option<String>,
+
+    /// Benchmark files to add to existing plot data, without the `.json` extension, e.g. `fib-abc1234,fib-def5678`.
+    /// Defaults to every file ending in the current commit's short SHA
+    #[arg(
+        long,
+        env = "LURK_BENCH_FILES",
+        value_name = "FILES",
+        value_delimiter = ','
+    )]
+    bench_files: Vec<String>,
+
+    /// Add the bench files matching this glob in the input directory, e.g. `fibonacci-*-abc1234.json`, or
+    /// `**/*.json` to search subdirectories. Takes precedence over `--bench-files`
+    #[arg(long, value_name = "PATTERN")]
+    glob: Option<glob::Pattern>,
+
+    /// Directory to read benchmark JSON files from
+    #[arg(long, value_name = "PATH", default_value = ".")]
+    input_dir: Utf8PathBuf,
+
+    /// Directory to write charts and `plot-data.json` to, created if it doesn't exist. Existing plot data is read from here too.
+    /// Defaults to the working directory
+    #[arg(long, value_name = "PATH")]
+    output_dir: Option<Utf8PathBuf>,
+
+    /// Also write every stored point to this CSV file, as rows of `group,params,commit_date,time_ns`
+    #[arg(long, value_name = "PATH")]
+    export_csv: Option<Utf8PathBuf>,
+
+    /// Read benchmark JSON from stdin instead of bench files, e.g. `cat estimates.json | benchmark-plotter --stdin`
+    #[arg(long)]
+    stdin: bool,
+
+    /// Combine these `plot-data.json` files, e.g. from parallel CI shards, instead of adding bench files.
+    /// Points for the same commit are resolved by `--on-conflict`
+    #[arg(
+        long,
+        value_name = "FILE",
+        num_args = 1..,
+        conflicts_with_all = ["stdin", "glob", "bench_files"]
+    )]
+    merge: Vec<Utf8PathBuf>,
+
+    /// Also write a Markdown table comparing each line's latest result to the previous one, e.g. for a PR comment
+    #[arg(long, value_name = "PATH")]
+    export_markdown: Option<Utf8PathBuf>,
+
+    /// Also write the latest value of each line to this file in Prometheus' text format, for the node exporter's
+    /// textfile collector, e.g. `benchmark_time_ns{group="Fibonacci-num=10",params="rc=100"} 2010000`
+    #[arg(long, value_name = "PATH")]
+    prometheus: Option<Utf8PathBuf>,
+
+    /// Also export every point in InfluxDB's line protocol, e.g. `benchmark,group=Fibonacci-num\=10,params=rc\=100
+    /// time_ns=2010000 1704067200000000000`: written to this file for `influx write`, or posted to it if it's an
+    /// `http(s)://` write URL, which needs the `http` feature
+    #[arg(long, value_name = "PATH|URL")]
+    influx: Option<String>,
+
+    /// Also write a self-contained HTML report to this file, with a chart per group showing each point's commit and value
+    /// on hover
+    #[arg(long, value_name = "PATH")]
+    html: Option<Utf8PathBuf>,
+
+    /// Also make an SVG badge of the latest value of this line, e.g. `Fibonacci-num=10/rc=100`, written to `--badge-out`
+    #[arg(long, value_name = "GROUP/PARAMS", requires = "badge_out")]
+    badge: Option<String>,
+
+    /// File to write the `--badge` to, e.g. `badge.svg`
+    #[arg(long, value_name = "PATH", requires = "badge")]
+    badge_out: Option<Utf8PathBuf>,
+
+    /// Compare two commits head-to-head instead of plotting the history: writes a bar chart per group of each params'
+    /// values at both, and prints a table of them. The plot data and usual charts are left as they are
+    #[arg(long, num_args = 2, value_names = ["BASE", "HEAD"], value_parser = parse_sha)]
+    compare: Vec<String>,
+
+    /// Git repository to look up each point's commit message in, shown next to highlighted changes and in `--html`
+    /// tooltips. Commits it doesn't have, e.g. in a shallow clone, are shown by SHA alone
+    #[arg(long, value_name = "PATH")]
+    git_dir: Option<Utf8PathBuf>,
+
+    #[cfg(feature = "http")]
+    #[command(flatten)]
+    github: GithubArgs,
+
+    #[cfg(feature = "http")]
+    #[command(flatten)]
+    slack: SlackArgs,
+
+    #[cfg(feature = "http")]
+    #[command(flatten)]
+    influx_auth: InfluxArgs,
+
+    /// Print the effective config, after applying the config file and flags, and exit
+    #[arg(long, value_enum, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "toml")]
+    pub print_config: Option<ConfigFormat>,
+
+    /// Smoothing applied to each plotted line: `none`, `sma:<window>`, `ema:<alpha>` or `median:<window>`
+    #[arg(long)]
+    smoothing: Option<Smoothing>,
+
+    /// Overlay each line's moving average over this many points; shorthand for `--smoothing sma:<N>`
+    #[arg(long, value_name = "N", conflicts_with = "smoothing")]
+    smooth_window: Option<NonZeroU32>,
+
+    /// Horizontal alignment of the chart caption
+    #[arg(long, value_enum)]
+    caption_align: Option<CaptionAlign>,
+
+    /// How to fit a caption that is wider than the image
+    #[arg(long, value_enum)]
+    caption_overflow: Option<CaptionOverflow>,
+
+    /// Also draw each line's best-ever (cumulative minimum) value
+    #[arg(long)]
+    best_ever: bool,
+
+    /// Draw each point's confidence interval as a vertical error bar
+    #[arg(long)]
+    error_bars: bool,
+
+    /// Shade a band behind each line between its confidence bounds, or its rolling min and max
+    #[arg(long)]
+    show_band: bool,
+
+    /// Label each point with its commit's short SHA, skipping labels that would overlap
+    #[arg(long)]
+    sha_labels: bool,
+
+    /// Friendly name for a benchmark group, as `<group>=><alias>`; may be repeated
+    #[arg(long = "group-alias", value_name = "GROUP=>ALIAS", value_parser = parse_group_alias)]
+    group_aliases: Vec<(String, String)>,
+
+    /// Colors of specific params lines, e.g. `rc=100:#ff0000,rc=200:#00ff00`. Other lines keep a
+    /// color picked from their params
+    #[arg(
+        long = "colors",
+        value_name = "PARAMS:COLOR",
+        value_delimiter = ',',
+        value_parser = parse_line_color
+    )]
+    line_colors: Vec<(String, LineColor)>,
+
+    /// Draw a dashed reference line at this value, e.g. `Fibonacci-num=10/rc=100=1.5ms`, or `Fibonacci-num=10=2ms` for
+    /// the whole group, tinting the points on the wrong side of it; may be repeated. Values without a unit are in the
+    /// plot's stored units
+    #[arg(long = "baseline", value_name = "GROUP[/PARAMS]=VALUE", value_parser = parse_baseline)]
+    baselines: Vec<(String, Baseline)>,
+
+    /// Also merge groups sharing an alias into a single plot when adding data
+    #[arg(long)]
+    merge_group_aliases: bool,
+
+    /// Name charts with a content hash (e.g. `fibonacci.a1b2c3d4.png`) and write `manifest.json`
+    #[arg(long)]
+    hashed_names: bool,
+
+    /// Which of Criterion's estimates to plot as the time, or `throughput` for bytes or elements per second
+    #[arg(long, value_enum)]
+    metric: Option<Metric>,
+
+    /// How to resolve a new value for a commit that already has a stored point
+    #[arg(long, value_enum)]
+    on_conflict: Option<ConflictPolicy>,
+
+    /// Render this group as a params-vs-commits heatmap instead of lines; may be repeated
+    #[arg(long = "heatmap", value_name = "GROUP")]
+    heatmap_groups: Vec<String>,
+
+    /// Also stack every group's chart into a single image with this filename
+    #[arg(long, value_name = "FILE_NAME")]
+    combined: Option<String>,
+
+    /// Also tile every group's chart into a grid in a single image with this filename
+    #[arg(long, value_name = "FILE_NAME")]
+    dashboard: Option<String>,
+
+    /// Use one legend for the combined image when all groups have the same params
+    #[arg(long)]
+    shared_legend: bool,
+
+    /// Date ingested results with the current `HEAD` commit from Git instead of parsing bench names
+    #[arg(long)]
+    stamp_head: bool,
+
+    /// Plot the integer at this JSON pointer (e.g. `/constraints`) as a count instead of the time estimate
+    #[arg(long, value_name = "JSON_POINTER")]
+    count_pointer: Option<String>,
+
+    /// Also plot value against the numeric part of each param at this commit: `latest` or a `YYYY-MM-DD` commit date
+    #[arg(long, value_name = "COMMIT_DATE")]
+    sweep_at: Option<SweepAt>,
+
+    /// Fix the X axis start of every line chart to this commit date (`YYYY-MM-DD`)
+    #[arg(long, value_name = "DATE")]
+    x_min: Option<NaiveDate>,
+
+    /// Fix the X axis end of every line chart to this commit date (`YYYY-MM-DD`), inclusive
+    #[arg(long, value_name = "DATE")]
+    x_max: Option<NaiveDate>,
+
+    /// Fix the Y axis start of every line chart to this value, in the plot's units
+    #[arg(long, value_name = "VALUE")]
+    y_min: Option<f64>,
+
+    /// Fix the Y axis end of every line chart to this value, in the plot's units
+    #[arg(long, value_name = "VALUE")]
+    y_max: Option<f64>,
+
+    /// Also draw the geometric mean across params at each commit, skipping commits missing some params (`complete`) or not (`available`)
+    #[arg(long, value_enum)]
+    geo_mean: Option<GeoMean>,
+
+    /// Chart file format; `vega` writes a Vega-Lite spec per group instead of an image
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+
+    /// Width in pixels of each chart [default: 1024]
+    #[arg(long, value_name = "PIXELS")]
+    width: Option<NonZeroU32>,
+
+    /// Height in pixels of each chart [default: 768]
+    #[arg(long, value_name = "PIXELS")]
+    height: Option<NonZeroU32>,
+
+    /// Multiply the output size, along with margins, fonts and strokes, e.g. `2` for HiDPI displays
+    #[arg(long, value_name = "FACTOR")]
+    scale: Option<NonZeroU32>,
+
+    /// PNG rasterization quality; `high` supersamples for smoother lines and text at the cost of render time
+    #[arg(long, value_enum)]
+    quality: Option<Quality>,
+
+    /// Color theme of the charts; `dark` suits dark dashboards
+    #[arg(long, value_enum)]
+    theme: Option<Theme>,
+
+    /// Lay out line charts by commit `date`, or evenly by commit order (`index`) with SHAs as labels
+    #[arg(long, value_enum)]
+    x_axis: Option<XAxis>,
+
+    /// Leave out the first N points of each line when rendering, without removing them from storage
+    #[arg(long, value_name = "N")]
+    skip_warmup: Option<usize>,
+
+    /// Chart filename template with `{group}`, `{date}`, `{sha}` and `{format}` placeholders, e.g. `bench-{group}-{date}`
+    #[arg(long, value_name = "TEMPLATE")]
+    output_stem: Option<String>,
+
+    /// Fractional slowdown from the previous commit reported as a regression, e.g. `0.05` for 5%, under every regression rule
+    #[arg(long, value_name = "FRACTION")]
+    regression_threshold: Option<f64>,
+
+    /// Fractional speedup from the previous commit that counts as an improvement; defaults to the regression threshold
+    #[arg(long, value_name = "FRACTION")]
+    improvement_threshold: Option<f64>,
+
+    /// How the latest result is compared to the previous one when checking for regressions
+    #[arg(long, value_enum)]
+    regression_rule: Option<RegressionRule>,
+
+    /// Exit with a non-zero status if any regression is found, e.g. to fail a PR's CI. This is the
+    /// default unless the config file turns it off
+    #[arg(long, overrides_with = "no_fail_on_regression")]
+    fail_on_regression: bool,
+
+    /// Only report regressions, exiting successfully even if some are found
+    #[arg(long)]
+    no_fail_on_regression: bool,
+
+    /// Mark each point that regressed from the previous one on line charts
+    #[arg(long)]
+    highlight_regressions: bool,
+
+    /// Mark each point that improved on the previous one on line charts
+    #[arg(long)]
+    highlight_improvements: bool,
+
+    /// Label each highlighted point with its percent change from the previous one
+    #[arg(long)]
+    highlight_labels: bool,
+
+    /// Label the step between each pair of consecutive points with its percent change, e.g. `+12.0%`
+    #[arg(long)]
+    show_deltas: bool,
+
+    /// Fractional change a step needs to be labeled by `--show-deltas`, e.g. `0.02` for 2%
+    #[arg(long, value_name = "FRACTION")]
+    delta_min_change: Option<f64>,
+
+    /// Opacity in [0, 1] of each plotted line, so overlapping lines show where they cluster
+    #[arg(long, value_name = "ALPHA")]
+    line_opacity: Option<f64>,
+
+    /// Render and report on only the groups matching this pattern, e.g. `Fibonacci-*`; may be repeated.
+    /// Every group is still kept in `plot-data.json`
+    #[arg(long, value_name = "PATTERN")]
+    only: Vec<String>,
+
+    /// Leave out the groups matching this pattern, even if they match `--only`; may be repeated
+    #[arg(long, value_name = "PATTERN")]
+    exclude: Vec<String>,
+
+    /// Leave out points older than this before the newest result, e.g. `90d`, `12w` or `36h`
+    #[arg(long, value_name = "AGE")]
+    max_age: Option<MaxAge>,
+
+    /// Keep only the newest N points of each line
+    #[arg(long, value_name = "N")]
+    max_points: Option<NonZeroUsize>,
+
+    /// Also drop the points left out by `--max-age` and `--max-points` from `plot-data.json`
+    #[arg(long)]
+    prune_storage: bool,
+
+    /// Print every line ranked by its coefficient of variation, noisiest first
+    #[arg(long)]
+    stability_report: bool,
+
+    /// Label the N noisiest lines, by coefficient of variation, with it in the chart legend
+    #[arg(long, value_name = "N")]
+    annotate_noisiest: Option<usize>,
+
+    /// `strftime` format for dates on charts and in reports, e.g. `%d.%m.%Y`
+    #[arg(long, value_name = "FORMAT")]
+    date_format: Option<String>,
+
+    /// Zone to show dates on charts and in reports in: `utc`, `local`, or an offset like `-05:00`
+    #[arg(long, value_name = "ZONE", allow_hyphen_values = true)]
+    timezone: Option<Timezone>,
+
+    /// Decimal separator for numbers on charts and in reports, e.g. `,`
+    #[arg(long, value_name = "SEPARATOR")]
+    decimal_separator: Option<String>,
+
+    /// Separator between groups of three integer digits on charts and in reports, e.g. `.`
+    #[arg(long, value_name = "SEPARATOR")]
+    thousands_separator: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+// Flags for posting the comparison table on a PR, which only exist with the `http` feature
+#[cfg(feature = "http")]
+#[derive(Debug, clap::Args)]
+pub struct GithubArgs {
+    /// Post the Markdown comparison table as a comment on the PR, editing the plotter's earlier comment if there is one
+    #[arg(long, requires_all = ["github_repo", "github_pr", "github_token"])]
+    pub github_comment: bool,
+
+    /// Repository of the PR, e.g. `lurk-lab/lurk-rs`
+    #[arg(long, env = "GITHUB_REPOSITORY", value_name = "OWNER/NAME")]
+    pub github_repo: Option<String>,
+
+    /// Number of the PR to comment on
+    #[arg(long, env = "GITHUB_PR_NUMBER", value_name = "NUMBER")]
+    pub github_pr: Option<u64>,
+
+    /// Token with permission to write PR comments, e.g. the workflow's `GITHUB_TOKEN`
+    #[arg(
+        long,
+        env = "GITHUB_TOKEN",
+        value_name = "TOKEN",
+        hide_env_values = true
+    )]
+    pub github_token: Option<String>,
+}
+
+// Flags for notifying Slack of regressions, which only exist with the `http` feature
+#[cfg(feature = "http")]
+#[derive(Debug, clap::Args)]
+pub struct SlackArgs {
+    /// Slack incoming webhook to post any regressions to, after the charts are saved
+    #[arg(
+        long,
+        env = "SLACK_WEBHOOK_URL",
+        value_name = "URL",
+        hide_env_values = true
+    )]
+    pub slack_webhook: Option<String>,
+
+    /// URL the output directory is published at, e.g. on GitHub Pages, to link each regression to its chart
+    #[arg(long, value_name = "URL")]
+    pub plot_url: Option<String>,
+}
+
+// Credentials for posting `--influx` points, which only exist with the `http` feature
+#[cfg(feature = "http")]
+#[derive(Debug, clap::Args)]
+pub struct InfluxArgs {
+    /// API token for an InfluxDB 2 `--influx` write URL
+    #[arg(
+        long,
+        env = "INFLUX_TOKEN",
+        value_name = "TOKEN",
+        hide_env_values = true
+    )]
+    pub influx_token: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Write a commented `ci-workflows.toml` with every option set to its default
+    Init {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+// Accepts a full or abbreviated commit SHA, which has to be hex so it can't match unrelated files,
+// and at least as long as the stored short SHAs so it doesn't match unrelated commits
+fn parse_sha(s: &str) -> Result<String, String> {
+    if s.len() >= 7 && s.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(s.to_ascii_lowercase())
+    } else {
+        Err(format!(
+            "Expected a hex commit SHA of at least 7 characters, found `{}`",
+            s
+        ))
+    }
+}
+
+impl Cli {
+    // Overrides the config file values with any flags given on the command line
+    pub fn apply(self, config: &mut Config) {
+        let Cli {
+            command: _,
+            config: _,
+            verbose: _,
+            force,
+            dry_run,
+            validate,
+            max_reject_rate,
+            sha,
+            bench_files,
+            glob,
+            input_dir,
+            output_dir,
+            export_csv,
+            export_markdown,
+            prometheus,
+            influx,
+            html,
+            badge,
+            badge_out,
+            compare,
+            git_dir,
+            #[cfg(feature = "http")]
+            github,
+            #[cfg(feature = "http")]
+            slack,
+            #[cfg(feature = "http")]
+            influx_auth,
+            stdin,
+            merge,
+            print_config: _,
+            smoothing,
+            smooth_window,
+            caption_align,
+            caption_overflow,
+            best_ever,
+            error_bars,
+            show_band,
+            sha_labels,
+            group_aliases,
+            line_colors,
+            baselines,
+            merge_group_aliases,
+            hashed_names,
+            metric,
+            on_conflict,
+            heatmap_groups,
+            combined,
+            dashboard,
+            shared_legend,
+            stamp_head,
+            count_pointer,
+            sweep_at,
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+            geo_mean,
+            format,
+            width,
+            height,
+            scale,
+            quality,
+            theme,
+            x_axis,
+            skip_warmup,
+            output_stem,
+            regression_threshold,
+            improvement_threshold,
+            regression_rule,
+            fail_on_regression,
+            no_fail_on_regression,
+            highlight_regressions,
+            highlight_improvements,
+            highlight_labels,
+            show_deltas,
+            delta_min_change,
+            line_opacity,
+            only,
+            exclude,
+            max_age,
+            max_points,
+            prune_storage,
+            stability_report,
+            annotate_noisiest,
+            date_format,
+            timezone,
+            decimal_separator,
+            thousands_separator,
+        } = self;
+        if let Some(dir) = output_dir {
+            config.output_dir = Some(dir.into_string());
+        }
+        config.run = RunOptions {
+            force,
+            stdin,
+            dry_run,
+            validate,
+            max_reject_rate,
+            bench_files,
+            glob,
+            merge,
+            sha,
+            input_dir,
+            export_csv,
+            export_markdown,
+            prometheus,
+            influx,
+            html,
+            badge: badge.zip(badge_out),
+            compare,
+            git_dir,
+            #[cfg(feature = "http")]
+            slack_webhook: slack.slack_webhook,
+            #[cfg(feature = "http")]
+            plot_url: slack.plot_url,
+            #[cfg(feature = "http")]
+            influx_token: influx_auth.influx_token,
+            #[cfg(feature = "http")]
+            github: github.github_comment.then(|| PullRequest {
+                repo: github.github_repo.unwrap_or_default(),
+                number: github.github_pr.unwrap_or_default(),
+                token: github.github_token.unwrap_or_default(),
+            }),
+        };
+        let (ingest, chart) = (&mut config.ingest, &mut config.chart);
+
+        if count_pointer.is_some() {
+            ingest.count_pointer = count_pointer;
+        }
+        if let Some(metric) = metric {
+            ingest.metric = metric;
+        }
+        if let Some(on_conflict) = on_conflict {
+            ingest.on_conflict = on_conflict;
+        }
+        ingest.merge_group_aliases |= merge_group_aliases;
+        ingest.stamp_head |= stamp_head;
+
+        if let Some(smoothing) = smoothing {
+            chart.smoothing = smoothing;
+        }
+        if let Some(window) = smooth_window {
+            chart.smoothing = Smoothing::MovingAverage(window.get() as usize);
+        }
+        if let Some(align) = caption_align {
+            chart.caption.align = align;
+        }
+        if let Some(overflow) = caption_overflow {
+            chart.caption.overflow = overflow;
+        }
+        chart.best_ever |= best_ever;
+        chart.error_bars |= error_bars;
+        chart.show_band |= show_band;
+        chart.sha_labels |= sha_labels;
+        chart.group_aliases.extend(group_aliases);
+        chart.line_colors.extend(line_colors);
+        chart.baselines.extend(baselines);
+        chart.hashed_names |= hashed_names;
+        chart.heatmap_groups.extend(heatmap_groups);
+        if combined.is_some() {
+            chart.combined = combined;
+        }
+        if dashboard.is_some() {
+            chart.dashboard = dashboard;
+        }
+        chart.shared_legend |= shared_legend;
+        if sweep_at.is_some() {
+            chart.sweep_at = sweep_at;
+        }
+        let range = &mut chart.range;
+        range.x.min = x_min.or(range.x.min);
+        range.x.max = x_max.or(range.x.max);
+        range.y.min = y_min.or(range.y.min);
+        range.y.max = y_max.or(range.y.max);
+        if let Some(geo_mean) = geo_mean {
+            chart.geo_mean = geo_mean;
+        }
+        if let Some(format) = format {
+            chart.format = format;
+        }
+        chart.width = width.or(chart.width);
+        chart.height = height.or(chart.height);
+        chart.scale = scale.or(chart.scale);
+        if let Some(quality) = quality {
+            chart.quality = quality;
+        }
+        if let Some(theme) = theme {
+            chart.theme = theme;
+        }
+        if let Some(x_axis) = x_axis {
+            chart.x_axis = x_axis;
+        }
+        if let Some(skip_warmup) = skip_warmup {
+            chart.skip_warmup = skip_warmup;
+        }
+        if output_stem.is_some() {
+            chart.output_stem = output_stem;
+        }
+        if line_opacity.is_some() {
+            chart.line_opacity = line_opacity;
+        }
+        if let Some(n) = annotate_noisiest {
+            chart.annotate_noisiest = n;
+        }
+        chart.highlight.regressions |= highlight_regressions;
+        chart.highlight.improvements |= highlight_improvements;
+        chart.deltas.show |= show_deltas;
+        if let Some(min_change) = delta_min_change {
+            chart.deltas.min_change = min_change;
+        }
+        chart.highlight.labels |= highlight_labels;
+
+        let regression = &mut config.regression;
+        if let Some(threshold) = regression_threshold {
+            regression.threshold = threshold;
+        }
+        if improvement_threshold.is_some() {
+            regression.improvement_threshold = improvement_threshold;
+        }
+        if let Some(rule) = regression_rule {
+            regression.rule = rule;
+        }
+        if fail_on_regression {
+            regression.fail = true;
+        }
+        if no_fail_on_regression {
+            regression.fail = false;
+        }
+
+        let retention = &mut config.retention;
+        retention.max_age = max_age.or(retention.max_age);
+        retention.max_points = max_points.or(retention.max_points);
+        retention.prune_storage |= prune_storage;
+
+        config.groups.only.extend(only);
+        config.groups.exclude.extend(exclude);
+
+        config.report.stability |= stability_report;
+
+        let locale = &mut config.locale;
+        if date_format.is_some() {
+            locale.date_format = date_format;
+        }
+        if let Some(timezone) = timezone {
+            locale.timezone = timezone;
+        }
+        if let Some(separator) = decimal_separator {
+            locale.decimal_separator = separator;
+        }
+        if let Some(separator) = thousands_separator {
+            locale.thousands_separator = separator;
+        }
+    }
+}
@@ -0,0 +1,953 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    num::{NonZeroU32, NonZeroUsize},
+    path::Path,
+    str::FromStr,
+};
+
+use chrono::{Duration, NaiveDate};
+
+use anyhow::{anyhow, Context};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::git::CommitInfo;
+use crate::json::BenchId;
+use crate::locale::Locale;
+use crate::run::RunOptions;
+
+// Commented config file with every option set to its default, written by the `init` command
+pub const DEFAULT_CONFIG_TOML: &str = include_str!("default-config.toml");
+
+// All settings, read from `ci-workflows.toml` if present and then overridden by command-line flags
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Directory charts and `plot-data.json` are written to, and existing plot data read from.
+    // Defaults to the working directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_dir: Option<String>,
+    pub ingest: IngestConfig,
+    pub retention: RetentionConfig,
+    pub groups: GroupFilter,
+    pub chart: ChartConfig,
+    pub regression: RegressionConfig,
+    pub report: ReportConfig,
+    pub locale: Locale,
+    // What this run reads and writes, set from the command line
+    #[serde(skip)]
+    pub run: RunOptions,
+}
+
+impl Config {
+    pub const DEFAULT_PATH: &'static str = "ci-workflows.toml";
+
+    // Reads the config file at `path`, or at `DEFAULT_PATH` if none is given. A missing file at the
+    // default path means the defaults are used, while a given path must exist
+    pub fn load<P: AsRef<Path>>(path: Option<P>) -> anyhow::Result<Self> {
+        let required = path.is_some();
+        let path = path
+            .as_ref()
+            .map_or(Path::new(Self::DEFAULT_PATH), AsRef::as_ref);
+        match std::fs::read_to_string(path) {
+            Ok(s) => {
+                toml::from_str(&s).with_context(|| format!("Invalid config {}", path.display()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !required => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read config {}", path.display())),
+        }
+    }
+}
+
+// Options controlling how each chart is rendered by `generate_plots`
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChartConfig {
+    pub smoothing: Smoothing,
+    pub caption: CaptionConfig,
+    // Draw each line's cumulative minimum ("best ever") value alongside it
+    pub best_ever: bool,
+    // Draw each point's confidence interval, if the benchmark reported one, as a vertical bar
+    pub error_bars: bool,
+    // Shade a band behind each line between its confidence bounds, or its rolling min and max for
+    // lines without them
+    pub show_band: bool,
+    // Label each point with its commit's short SHA
+    pub sha_labels: bool,
+    // Display names used for chart captions and output filenames
+    pub group_aliases: GroupAliases,
+    // Suffix output filenames with a content hash and write a `manifest.json` mapping groups to them
+    pub hashed_names: bool,
+    // Groups rendered as a params-vs-commits grid colored by value instead of one line per param
+    pub heatmap_groups: Vec<String>,
+    // Also stack every group's chart into a single image with this filename
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub combined: Option<String>,
+    // Also tile every group's chart into a roughly square grid in a single image with this filename
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dashboard: Option<String>,
+    // Draw one legend for the combined image instead of one per chart, if all groups share params
+    pub shared_legend: bool,
+    // Also draw each group's value-vs-param sweep profile at this commit, as `<group>.sweep.png`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sweep_at: Option<SweepAt>,
+    // Fixed axis bounds for every line chart, replacing the ones computed from the data
+    pub range: RangeConfig,
+    // Fixed axis bounds for specific groups, keyed by group name. Each bound given here takes
+    // precedence over the same bound in `range`
+    pub group_ranges: BTreeMap<String, RangeConfig>,
+    // Also draw the geometric mean of every params' values at each commit as a single bold line
+    pub geo_mean: GeoMean,
+    // Size in pixels each chart is laid out at, defaulting to 1024x768
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<NonZeroU32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<NonZeroU32>,
+    // Multiplies the output size, along with every margin, font and stroke. Defaults to 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<NonZeroU32>,
+    // Sweep profiles and the combined image have no Vega-Lite form and are skipped for `Vega`
+    pub format: OutputFormat,
+    pub quality: Quality,
+    pub theme: Theme,
+    pub x_axis: XAxis,
+    // Full SHAs of the repository's commits, oldest first, which order `XAxis::Index` charts
+    #[serde(skip)]
+    pub history: Option<Vec<String>>,
+    // Subjects of the commits points were benchmarked at, keyed by short SHA, from `--git-dir`
+    #[serde(skip)]
+    pub commit_messages: HashMap<String, String>,
+    // Leave out the first `n` points of each line when rendering. They're still kept in storage
+    pub skip_warmup: usize,
+    // Label the `n` lines with the highest coefficient of variation across all groups with it
+    pub annotate_noisiest: usize,
+    pub highlight: HighlightConfig,
+    pub deltas: DeltaConfig,
+    // Copied from `Config::regression`, which decides the changes that are highlighted
+    #[serde(skip)]
+    pub regression: RegressionConfig,
+    // Opacity in [0, 1] of each plotted line and its points, so dense overlapping lines stay
+    // distinguishable. Unset means fully opaque
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_opacity: Option<f64>,
+    // Template for chart filenames, with `{group}`, `{date}`, `{sha}` and `{format}` placeholders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_stem: Option<String>,
+    // Goal values for specific groups, keyed by group name. The first commit with a value below
+    // its group's target, or at or above it for throughput, is marked on the chart
+    pub targets: BTreeMap<String, f64>,
+    // Reference values drawn as a dashed line labeled "baseline", keyed by `<group>/<params>` or
+    // by `<group>` for the whole group. Points on the wrong side of their line's baseline, e.g.
+    // slower, are tinted
+    pub baselines: BTreeMap<String, Baseline>,
+    // Colors of specific params lines in every group, keyed by params e.g. `rc=100`. Other lines
+    // get a palette color picked by a hash of their params, so they keep it across runs too
+    pub line_colors: BTreeMap<String, LineColor>,
+    // Subdirectories for groups matching a pattern, tried in order. Groups matching none are
+    // written to `default_output_dir`, or alongside the other outputs if it isn't set
+    pub output_dirs: Vec<OutputDir>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_output_dir: Option<String>,
+    // Short SHA of the commit being plotted, for the `{sha}` filename placeholder
+    #[serde(skip)]
+    pub commit_sha: String,
+}
+
+impl ChartConfig {
+    // The subdirectory that `group`'s charts are written to, if any
+    pub fn output_dir_for(&self, group: &str) -> Option<&str> {
+        self.output_dirs
+            .iter()
+            .find(|dir| glob_match(&dir.pattern, group))
+            .map(|dir| dir.dir.as_str())
+            .or(self.default_output_dir.as_deref())
+    }
+
+    // Width and height in pixels each chart is laid out at, before `scale`
+    pub fn size(&self) -> (u32, u32) {
+        (
+            self.width.map_or(1024, NonZeroU32::get),
+            self.height.map_or(768, NonZeroU32::get),
+        )
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale.map_or(1, NonZeroU32::get)
+    }
+
+    // The axis bounds for `group`, combining its own overrides with the global ones
+    pub fn range_for(&self, group: &str) -> RangeConfig {
+        match self.group_ranges.get(group) {
+            Some(group_range) => group_range.or(&self.range),
+            None => self.range.clone(),
+        }
+    }
+}
+
+// Marks on line charts for changes between consecutive points beyond the `[regression]` thresholds
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HighlightConfig {
+    pub regressions: bool,
+    pub improvements: bool,
+    // Label each mark with the percent change from the previous commit
+    pub labels: bool,
+}
+
+// Labels on line charts between consecutive points, giving each step's percent change
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DeltaConfig {
+    pub show: bool,
+    // Fractional change a step needs to be labeled, so noise doesn't clutter the chart
+    pub min_change: f64,
+}
+
+impl Default for DeltaConfig {
+    fn default() -> Self {
+        Self {
+            show: false,
+            min_change: 0.02,
+        }
+    }
+}
+
+// Limits on how much of each line's history is rendered, applied after adding data
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    // Leave out points older than this before the newest point of any group
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<MaxAge>,
+    // Keep only the newest `n` points of each line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_points: Option<NonZeroUsize>,
+    // Also drop the left out points from `plot-data.json`, instead of only from the charts
+    pub prune_storage: bool,
+}
+
+impl RetentionConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.max_age.is_some() || self.max_points.is_some()
+    }
+}
+
+// Patterns selecting the groups that are rendered and reported on, matched by `glob_match`. Every
+// group is still kept in `plot-data.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GroupFilter {
+    // Only the groups matching one of these, or every group if it's empty
+    pub only: Vec<String>,
+    // Leave out the groups matching any of these, even if they match `only`
+    pub exclude: Vec<String>,
+}
+
+impl GroupFilter {
+    pub fn is_enabled(&self) -> bool {
+        !self.only.is_empty() || !self.exclude.is_empty()
+    }
+
+    pub fn includes(&self, group: &str) -> bool {
+        let matches = |patterns: &[String]| patterns.iter().any(|p| glob_match(p, group));
+        (self.only.is_empty() || matches(&self.only)) && !matches(&self.exclude)
+    }
+}
+
+// A span of time written as a number of hours, days or weeks, e.g. `90d`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct MaxAge(pub Duration);
+
+impl FromStr for MaxAge {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s.len() - s.chars().last().map_or(0, char::len_utf8);
+        let (n, unit) = s.split_at(split);
+        let n = n
+            .parse::<i64>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| anyhow!("Expected a positive age like `90d`, got `{s}`"))?;
+        match unit {
+            "h" => Ok(MaxAge(Duration::hours(n))),
+            "d" => Ok(MaxAge(Duration::days(n))),
+            "w" => Ok(MaxAge(Duration::weeks(n))),
+            _ => Err(anyhow!(
+                "Unknown age unit in `{s}`, expected `h`, `d` or `w`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for MaxAge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hours = self.0.num_hours();
+        if hours % (24 * 7) == 0 {
+            write!(f, "{}w", hours / (24 * 7))
+        } else if hours % 24 == 0 {
+            write!(f, "{}d", hours / 24)
+        } else {
+            write!(f, "{hours}h")
+        }
+    }
+}
+
+impl From<MaxAge> for String {
+    fn from(age: MaxAge) -> Self {
+        age.to_string()
+    }
+}
+
+impl TryFrom<String> for MaxAge {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+// Optional reports printed after adding data
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReportConfig {
+    // Rank every line by its coefficient of variation, noisiest first
+    pub stability: bool,
+}
+
+// Options controlling which changes in the latest results are reported as regressions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegressionConfig {
+    // Fractional slowdown from the previous commit that counts as a regression, e.g. `0.05` for 5%.
+    // Every rule requires it, the interval rules on top of their own check
+    pub threshold: f64,
+    // Fractional speedup that counts as an improvement. Defaults to `threshold`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub improvement_threshold: Option<f64>,
+    pub rule: RegressionRule,
+    // Exit with a non-zero status if any regression is found, to gate CI on it
+    pub fail: bool,
+}
+
+impl Default for RegressionConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 0.05,
+            improvement_threshold: None,
+            rule: RegressionRule::default(),
+            fail: true,
+        }
+    }
+}
+
+// How the latest point is compared to the previous one. Interval rules fall back to `Estimate`
+// when either point has no confidence interval
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RegressionRule {
+    // The estimate grew by more than the threshold
+    Estimate,
+    // The estimate grew by more than the threshold, and the new interval lies entirely above the
+    // previous one, so noise can't explain the change
+    #[default]
+    Disjoint,
+    // The new interval's lower bound exceeds the previous upper bound by more than the threshold
+    Strict,
+}
+
+// Options controlling how benchmark results are read and added to `Plots`
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IngestConfig {
+    // Plot the integer at this JSON pointer as a count instead of the time estimate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count_pointer: Option<String>,
+    // Which of Criterion's estimates is plotted as the time
+    pub metric: Metric,
+    // How to resolve a new value for a commit that already has a stored point
+    pub on_conflict: ConflictPolicy,
+    // Merge groups sharing an alias in `ChartConfig::group_aliases` into a single plot
+    pub merge_group_aliases: bool,
+    // Date every ingested result with the `HEAD` commit resolved from Git, rather than parsing the
+    // date from the bench name. Falls back to the bench name if Git resolution fails
+    pub stamp_head: bool,
+    // Where to find the values' unit in bench IDs, for teams whose names already carry it. Only
+    // used for results without a unit from Criterion
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit_hint: Option<UnitHint>,
+    pub bench_id: BenchIdFormat,
+    // Aliases applied to group names before storing, copied from the chart config when merging
+    #[serde(skip)]
+    pub group_aliases: GroupAliases,
+    // The `HEAD` commit used when `stamp_head` is set and Git resolution succeeded
+    #[serde(skip)]
+    pub head_commit: Option<CommitInfo>,
+}
+
+// File format of each group's chart
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    // Scalable vector image, for web dashboards and docs
+    Svg,
+    // Vega-Lite JSON spec with the data embedded, for interactive charts in a web frontend
+    Vega,
+}
+
+impl OutputFormat {
+    // File extension, also substituted for `{format}` in `ChartConfig::output_stem`
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Svg => "svg",
+            OutputFormat::Vega => "vl.json",
+        }
+    }
+}
+
+// Rasterization quality of PNG output
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Quality {
+    // Draw directly at the output size
+    #[default]
+    Standard,
+    // Draw at several times the output size and downsample, smoothing jagged lines and text
+    High,
+}
+
+// Colors of everything but the data in PNG and SVG charts. Line colors are lightened on `Dark`
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    // Black text and axes on white
+    #[default]
+    Light,
+    // Light text and axes on a near-black background, for dark dashboards
+    Dark,
+}
+
+// What the X axis of PNG and SVG line charts is laid out by
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum XAxis {
+    // Commit date, so the spacing shows the time between commits
+    #[default]
+    Date,
+    // Commit order, evenly spaced and labeled by SHA, for histories with rebased or backdated
+    // commits. Commits are in Git's order if the history can be read, and in date order otherwise
+    Index,
+}
+
+// Whether to draw a line chart's geometric-mean line, and which commits it covers
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GeoMean {
+    #[default]
+    Off,
+    // Only commits where every params has a value, so the line always aggregates the same params
+    Complete,
+    // Every commit, over whichever params have a value there
+    Available,
+}
+
+// Location of a unit token within a bench ID `<group>/<bench>/<params>`: the text after the last
+// `separator` in `segment`, or the whole segment if no separator is given
+// E.g. `{ segment = "group", separator = "-" }` reads `ns` from `prove-ns/<bench>/<params>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UnitHint {
+    pub segment: IdSegment,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub separator: Option<String>,
+}
+
+impl UnitHint {
+    // The unit token in `id`, or `None` if the segment doesn't contain the separator
+    pub fn parse<'a>(&self, id: &'a BenchId) -> Option<&'a str> {
+        let segment = match self.segment {
+            IdSegment::Group => &id.group_name,
+            IdSegment::Bench => &id.bench_name,
+            IdSegment::Params => &id.params,
+        };
+        let unit = match &self.separator {
+            Some(separator) => segment.rsplit_once(separator.as_str())?.1,
+            None => segment,
+        };
+        (!unit.is_empty()).then_some(unit)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IdSegment {
+    Group,
+    Bench,
+    Params,
+}
+
+// How bench IDs are split into their group, bench name and params, for harnesses with their own
+// naming scheme. The default reads Criterion's `<group>/<bench>/<params>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BenchIdFormat {
+    #[serde(deserialize_with = "non_empty")]
+    pub separator: String,
+    // The part each segment of the ID is, in order. The last keeps any further segments, e.g.
+    // params like `rc=100/extra=foo`, and IDs that stop before their params get empty ones
+    #[serde(deserialize_with = "id_segments")]
+    pub segments: Vec<IdSegment>,
+    // Turn the `_`s in the bench name back into `:`s, which Criterion replaces in timestamps
+    pub restore_colons: bool,
+}
+
+impl Default for BenchIdFormat {
+    fn default() -> Self {
+        BenchIdFormat {
+            separator: "/".to_owned(),
+            segments: vec![IdSegment::Group, IdSegment::Bench, IdSegment::Params],
+            restore_colons: true,
+        }
+    }
+}
+
+fn non_empty<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    if s.is_empty() {
+        return Err(serde::de::Error::custom("Expected a non-empty separator"));
+    }
+    Ok(s)
+}
+
+// Every ID needs a group and a bench name to read the commit from, while params are optional
+fn id_segments<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<IdSegment>, D::Error> {
+    let segments = Vec::<IdSegment>::deserialize(deserializer)?;
+    let count = |segment| segments.iter().filter(|s| **s == segment).count();
+    if count(IdSegment::Group) != 1 || count(IdSegment::Bench) != 1 || count(IdSegment::Params) > 1
+    {
+        return Err(serde::de::Error::custom(
+            "Expected `group` and `bench` segments and at most one `params`, each only once",
+        ));
+    }
+    Ok(segments)
+}
+
+// One of the estimates Criterion reports for each benchmark
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Metric {
+    Mean,
+    // Robust to the occasional outlier sample, e.g. from a GC pause or a busy CI runner
+    Median,
+    #[default]
+    Typical,
+    // Slope of the linear regression over iterations, absent unless linear sampling was used
+    Slope,
+    // Bytes or elements per second, from the `typical` time and the throughput the benchmark was
+    // configured with. Records without a throughput are skipped
+    Throughput,
+}
+
+impl Metric {
+    // Name of the estimate's field in each JSON record, also shown in axis descriptions
+    pub fn field(self) -> &'static str {
+        match self {
+            Metric::Mean => "mean",
+            Metric::Median => "median",
+            Metric::Typical => "typical",
+            Metric::Slope => "slope",
+            // The rate is computed from the typical time
+            Metric::Throughput => "typical",
+        }
+    }
+}
+
+// Resolution for a new value with the same group, params and commit date as a stored point
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    KeepExisting,
+    Overwrite,
+    // Keep the lower value, which is usually the least noisy sample of a benchmark
+    #[default]
+    KeepMin,
+    // Abort if the values differ
+    Error,
+}
+
+// Map of raw, often machine-generated, group names to friendly ones
+// E.g. `bench::prove::fibonacci::v2=>Fibonacci prove`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GroupAliases(BTreeMap<String, String>);
+
+impl GroupAliases {
+    // Returns the alias for `group`, or `group` itself if it isn't mapped
+    pub fn resolve<'a>(&'a self, group: &'a str) -> &'a str {
+        self.0.get(group).map_or(group, String::as_str)
+    }
+}
+
+// Parses a single `<raw>=><friendly>` alias
+// `=>` is used as the delimiter since group names commonly contain `=` and `:`
+pub fn parse_group_alias(s: &str) -> anyhow::Result<(String, String)> {
+    match s.split_once("=>") {
+        Some((raw, alias)) if !raw.is_empty() && !alias.is_empty() => {
+            Ok((raw.to_owned(), alias.to_owned()))
+        }
+        _ => Err(anyhow!("Expected `<group>=><alias>`, got `{s}`")),
+    }
+}
+
+impl Extend<(String, String)> for GroupAliases {
+    fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+// An RGB color written as `#rrggbb`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct LineColor(pub u8, pub u8, pub u8);
+
+impl FromStr for LineColor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6 && hex.is_ascii())
+            .ok_or_else(|| anyhow!("Expected a color `#rrggbb`, got `{s}`"))?;
+        let channel = |i: usize| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| anyhow!("Expected a color `#rrggbb`, got `{s}`"))
+        };
+        Ok(LineColor(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+impl fmt::Display for LineColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+impl From<LineColor> for String {
+    fn from(color: LineColor) -> Self {
+        color.to_string()
+    }
+}
+
+impl TryFrom<String> for LineColor {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+// Parses a single `<params>:<color>` line color, e.g. `rc=100:#ff0000`. The color is split off at
+// the last `:`, since params may contain one
+pub fn parse_line_color(s: &str) -> anyhow::Result<(String, LineColor)> {
+    match s.rsplit_once(':') {
+        Some((params, color)) if !params.is_empty() => Ok((params.to_owned(), color.parse()?)),
+        _ => Err(anyhow!("Expected `<params>:#rrggbb`, got `{s}`")),
+    }
+}
+
+// A value with an optional unit after it, e.g. `1.5ms`, `256 MB` or `1500000` for one in the plot's
+// stored units. Units are converted to the stored ones when the chart is drawn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Baseline {
+    pub value: f64,
+    pub unit: Option<String>,
+}
+
+impl FromStr for Baseline {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The number is the longest prefix that parses as one, so exponents like `1e6ns` work
+        let number = (1..=s.len())
+            .rev()
+            .filter(|&i| s.is_char_boundary(i))
+            .find_map(|i| Some((i, s[..i].trim().parse::<f64>().ok()?)));
+        match number {
+            Some((end, value)) if value.is_finite() => {
+                let unit = s[end..].trim();
+                Ok(Baseline {
+                    value,
+                    unit: (!unit.is_empty()).then(|| unit.to_owned()),
+                })
+            }
+            _ => Err(anyhow!("Expected a value like `1.5ms`, got `{s}`")),
+        }
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            self.value,
+            self.unit.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+impl From<Baseline> for String {
+    fn from(baseline: Baseline) -> Self {
+        baseline.to_string()
+    }
+}
+
+impl TryFrom<String> for Baseline {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+// Parses a single `<group>[/<params>]=<value>` baseline, e.g. `Fibonacci-num=10/rc=100=1.5ms`.
+// The value is split off at the last `=`, since group names and params may contain one
+pub fn parse_baseline(s: &str) -> anyhow::Result<(String, Baseline)> {
+    match s.rsplit_once('=') {
+        Some((line, value)) if !line.is_empty() => Ok((line.to_owned(), value.parse()?)),
+        _ => Err(anyhow!("Expected `<group>[/<params>]=<value>`, got `{s}`")),
+    }
+}
+
+// Charts of groups whose name matches `pattern` are written to the `dir` subdirectory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputDir {
+    pub pattern: String,
+    pub dir: String,
+}
+
+// Matches `s` against a pattern where `*` matches any run of chars and `?` any single char
+pub fn glob_match(pattern: &str, s: &str) -> bool {
+    let (pattern, s) = (
+        pattern.chars().collect::<Vec<_>>(),
+        s.chars().collect::<Vec<_>>(),
+    );
+    // Backtracking point: the index after the latest `*`, and the first char of `s` it may cover
+    let (mut p, mut i, mut star) = (0, 0, None);
+    while i < s.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, i));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == s[i] => {
+                p += 1;
+                i += 1;
+            }
+            _ => match star {
+                // Let the `*` cover one more char and retry
+                Some((star_p, star_i)) => {
+                    star = Some((star_p, star_i + 1));
+                    p = star_p;
+                    i = star_i + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// Optional fixed bounds for a line chart's axes. Unset bounds are computed from the data
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RangeConfig {
+    // Commit dates, with `max` inclusive of the whole day
+    pub x: AxisBounds<NaiveDate>,
+    // Values in the plot's units, e.g. nanoseconds
+    pub y: AxisBounds<f64>,
+}
+
+impl RangeConfig {
+    // Takes each bound from `self` if set, otherwise from `fallback`
+    fn or(&self, fallback: &RangeConfig) -> RangeConfig {
+        RangeConfig {
+            x: self.x.or(&fallback.x),
+            y: self.y.or(&fallback.y),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AxisBounds<T> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<T>,
+}
+
+impl<T: Copy> AxisBounds<T> {
+    fn or(&self, fallback: &AxisBounds<T>) -> AxisBounds<T> {
+        AxisBounds {
+            min: self.min.or(fallback.min),
+            max: self.max.or(fallback.max),
+        }
+    }
+}
+
+// Layout of the group name caption drawn at the top of each chart
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CaptionConfig {
+    pub align: CaptionAlign,
+    pub overflow: CaptionOverflow,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptionAlign {
+    Left,
+    #[default]
+    Center,
+}
+
+// What to do with a caption wider than the image
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptionOverflow {
+    // Break onto multiple lines, preferring to split after separators like `-` or `/`
+    Wrap,
+    // Cut off the end and append an ellipsis
+    #[default]
+    Truncate,
+}
+
+// Smoothing algorithm applied to each rendered line, e.g. `--smoothing median:5`
+// Windows are trailing, so the first points of a line use whatever history is available
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Smoothing {
+    #[default]
+    None,
+    // Mean of the last `n` points
+    MovingAverage(usize),
+    // Exponential moving average with weight `alpha` in (0, 1] given to the newest point
+    Ema(f64),
+    // Median of the last `n` points, which is robust to one-off spikes
+    Median(usize),
+}
+
+// Parses `none`, `sma:<window>`, `ema:<alpha>` or `median:<window>`
+impl FromStr for Smoothing {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "none" {
+            return Ok(Smoothing::None);
+        }
+        let (algo, param) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Expected `<algorithm>:<param>`, got `{s}`"))?;
+        let window = |param: &str| -> anyhow::Result<usize> {
+            match param.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(n),
+                _ => Err(anyhow!(
+                    "Smoothing window must be a positive integer, got `{param}`"
+                )),
+            }
+        };
+        match algo {
+            "sma" => Ok(Smoothing::MovingAverage(window(param)?)),
+            "median" => Ok(Smoothing::Median(window(param)?)),
+            "ema" => match param.parse::<f64>() {
+                Ok(alpha) if alpha > 0.0 && alpha <= 1.0 => Ok(Smoothing::Ema(alpha)),
+                _ => Err(anyhow!("EMA alpha must be in (0, 1], got `{param}`")),
+            },
+            _ => Err(anyhow!("Unknown smoothing algorithm `{algo}`")),
+        }
+    }
+}
+
+impl fmt::Display for Smoothing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Smoothing::None => write!(f, "none"),
+            Smoothing::MovingAverage(window) => write!(f, "sma:{window}"),
+            Smoothing::Ema(alpha) => write!(f, "ema:{alpha}"),
+            Smoothing::Median(window) => write!(f, "median:{window}"),
+        }
+    }
+}
+
+// Config files store smoothing in the same `<algorithm>:<param>` form as the command line
+impl From<Smoothing> for String {
+    fn from(smoothing: Smoothing) -> Self {
+        smoothing.to_string()
+    }
+}
+
+impl TryFrom<String> for Smoothing {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+// Commit whose results are drawn as a sweep profile, e.g. `--sweep-at 2024-01-15`
+// Only commit dates are stored in `Plots`, so a commit is selected by its date
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum SweepAt {
+    // Each group's most recent commit
+    Latest,
+    // The last commit on this date, for each group with results that day
+    Date(NaiveDate),
+}
+
+// Parses `latest` or a `YYYY-MM-DD` commit date
+impl FromStr for SweepAt {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "latest" {
+            return Ok(SweepAt::Latest);
+        }
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map(SweepAt::Date)
+            .map_err(|e| anyhow!("Expected `latest` or a `YYYY-MM-DD` date, got `{s}`: {e}"))
+    }
+}
+
+impl fmt::Display for SweepAt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SweepAt::Latest => write!(f, "latest"),
+            SweepAt::Date(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+        }
+    }
+}
+
+impl From<SweepAt> for String {
+    fn from(at: SweepAt) -> Self {
+        at.to_string()
+    }
+}
+
+impl TryFrom<String> for SweepAt {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
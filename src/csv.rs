@@ -0,0 +1,42 @@
+use std::fmt::Write;
+
+use crate::plot::{ns_per, Plots};
+
+// Every stored point as CSV rows of `group,params,commit_date,time_ns`, sorted by group, params
+// and date so the output of consecutive runs diffs cleanly. Times in a known unit are converted to
+// nanoseconds, and other values, e.g. counts, are written as stored
+pub fn plots_csv(plots: &Plots) -> String {
+    let mut csv = String::from("group,params,commit_date,time_ns\n");
+    for (group, plot) in plots.0.iter() {
+        let scale = plot
+            .unit
+            .as_deref()
+            .map_or(Some(1.0), ns_per)
+            .unwrap_or(1.0);
+        for (params, points) in plot.lines.iter() {
+            let mut points = points.iter().collect::<Vec<_>>();
+            points.sort_by_key(|p| p.x);
+            for p in points {
+                writeln!(
+                    csv,
+                    "{},{},{},{}",
+                    field(group),
+                    field(params),
+                    p.x.to_rfc3339(),
+                    p.y * scale
+                )
+                .unwrap();
+            }
+        }
+    }
+    csv
+}
+
+// Quotes a field containing a delimiter or quote, e.g. params like `n=10,m=20`
+fn field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
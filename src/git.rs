@@ -0,0 +1,94 @@
+use std::{collections::HashMap, process::Command};
+
+use anyhow::{anyhow, Context};
+use camino::Utf8Path;
+use chrono::{DateTime, Utc};
+use tracing::{debug, warn};
+
+// A commit resolved from the local Git repository at runtime
+#[derive(Debug, Clone)]
+pub struct CommitInfo {
+    pub sha: String,
+    pub date: DateTime<Utc>,
+}
+
+// Resolves the SHA and committer date of `HEAD` in the current directory's repository
+pub fn head_commit() -> anyhow::Result<CommitInfo> {
+    let output = Command::new("git")
+        .args(["show", "--no-patch", "--format=%H%n%cI", "HEAD"])
+        .output()
+        .context("Failed to run `git show`")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "`git show` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut lines = stdout.lines();
+    let (Some(sha), Some(date)) = (lines.next(), lines.next()) else {
+        return Err(anyhow!("Unexpected `git show` output: {stdout}"));
+    };
+    Ok(CommitInfo {
+        sha: sha.to_owned(),
+        date: DateTime::parse_from_rfc3339(date)?.with_timezone(&Utc),
+    })
+}
+
+// First line of the message of each commit in `shas`, from the repository at `dir`, keyed by the
+// SHA as given. Commits Git can't find, e.g. ones left out of a shallow clone, are left out too
+pub fn commit_subjects<'a>(
+    dir: &Utf8Path,
+    shas: impl IntoIterator<Item = &'a str>,
+) -> HashMap<String, String> {
+    let mut subjects = HashMap::new();
+    // Hand-edited plot data could hold anything, which Git shouldn't read as an option
+    for sha in shas
+        .into_iter()
+        .filter(|sha| sha.chars().all(|c| c.is_ascii_hexdigit()))
+    {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["log", "-1", "--format=%s", sha, "--"])
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {
+                let subject = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+                if !subject.is_empty() {
+                    subjects.insert(sha.to_owned(), subject);
+                }
+            }
+            Ok(output) => debug!(
+                "no commit {} in {}: {}",
+                sha,
+                dir,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => {
+                warn!("failed to run `git log`: {e}");
+                break;
+            }
+        }
+    }
+    subjects
+}
+
+// Full SHAs of every commit reachable from `HEAD`, oldest first, with parents always before their
+// children
+pub fn commit_history() -> anyhow::Result<Vec<String>> {
+    let output = Command::new("git")
+        .args(["rev-list", "--topo-order", "--reverse", "HEAD"])
+        .output()
+        .context("Failed to run `git rev-list`")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "`git rev-list` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .map(str::to_owned)
+        .collect())
+}
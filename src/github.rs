@@ -0,0 +1,94 @@
+use anyhow::{anyhow, Context};
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::Deserialize;
+use serde_json::json;
+
+// Hidden line at the start of the comment, so later runs find and edit it instead of posting again
+const MARKER: &str = "<!-- benchmark-plotter -->";
+
+// GitHub's REST API, or the one given by `GITHUB_API_URL`, which Actions sets on GitHub Enterprise
+const DEFAULT_API_URL: &str = "https://api.github.com";
+
+// Pull request to comment on, e.g. `lurk-lab/lurk-rs` #42
+#[derive(Debug)]
+pub struct PullRequest {
+    pub repo: String,
+    pub number: u64,
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+struct Comment {
+    id: u64,
+    body: Option<String>,
+}
+
+// Posts `body` as the plotter's comment on the PR, replacing the one from an earlier run if there is
+// one, so each PR keeps a single up-to-date comment. Returns the comment's URL
+pub fn upsert_comment(pr: &PullRequest, body: &str) -> anyhow::Result<String> {
+    let api = std::env::var("GITHUB_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_owned());
+    let api = api.trim_end_matches('/');
+    let client = Client::builder()
+        .user_agent(concat!("benchmark-plotter/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let request = |builder: RequestBuilder| {
+        builder
+            .bearer_auth(&pr.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+    };
+    let body = json!({ "body": format!("{}\n{}", MARKER, body) });
+
+    let existing = find_comment(&client, &request, api, pr)?;
+    let response = match existing {
+        Some(id) => request(client.patch(format!("{api}/repos/{}/issues/comments/{id}", pr.repo))),
+        None => request(client.post(format!(
+            "{api}/repos/{}/issues/{}/comments",
+            pr.repo, pr.number
+        ))),
+    }
+    .json(&body)
+    .send()?
+    .error_for_status()
+    .context("Failed to post the comment")?;
+
+    #[derive(Deserialize)]
+    struct Posted {
+        html_url: String,
+    }
+    Ok(response.json::<Posted>()?.html_url)
+}
+
+// ID of the earlier comment bearing the marker, searching every page of the PR's comments
+fn find_comment(
+    client: &Client,
+    request: &dyn Fn(RequestBuilder) -> RequestBuilder,
+    api: &str,
+    pr: &PullRequest,
+) -> anyhow::Result<Option<u64>> {
+    const PER_PAGE: usize = 100;
+    for page in 1.. {
+        let url = format!(
+            "{api}/repos/{}/issues/{}/comments?per_page={PER_PAGE}&page={page}",
+            pr.repo, pr.number
+        );
+        let comments = request(client.get(url))
+            .send()?
+            .error_for_status()
+            .with_context(|| format!("Failed to list the comments of {} #{}", pr.repo, pr.number))?
+            .json::<Vec<Comment>>()?;
+        let found = comments.iter().find(|comment| {
+            comment
+                .body
+                .as_deref()
+                .map_or(false, |body| body.starts_with(MARKER))
+        });
+        if let Some(comment) = found {
+            return Ok(Some(comment.id));
+        }
+        if comments.len() < PER_PAGE {
+            return Ok(None);
+        }
+    }
+    Err(anyhow!("Too many comments on {} #{}", pr.repo, pr.number))
+}
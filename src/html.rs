@@ -0,0 +1,57 @@
+use serde_json::{json, Value};
+
+use crate::config::ChartConfig;
+use crate::locale;
+use crate::plot::Plots;
+
+// Page the report's data is embedded in, along with the script that draws it
+const TEMPLATE: &str = include_str!("report.html");
+
+// Self-contained HTML page with a line chart per group, drawn in the browser from the embedded
+// points so hovering a point shows its params, commit, date and value. Needs no network access,
+// unlike Vega-Lite specs, so it can be attached to CI runs as-is
+pub fn html_report(plots: &Plots, config: &ChartConfig) -> String {
+    let locale = locale::current();
+    let groups = plots
+        .0
+        .iter()
+        .filter(|(_, plot)| plot.x_axis.bounds().is_some())
+        .map(|(group, plot)| {
+            let (desc, divisor) = plot.display_scale();
+            let unit = plot
+                .display_unit()
+                .map(|(unit, _)| unit)
+                .or(plot.unit.as_deref());
+            let lines = plot
+                .lines
+                .iter()
+                .map(|(params, points)| {
+                    let points = points
+                        .iter()
+                        .map(|p| {
+                            let value = locale.fixed(p.y / divisor, 2);
+                            json!({
+                                "x": p.x.timestamp_millis(),
+                                "y": p.y,
+                                "sha": p.sha,
+                                "message": p.sha.as_ref().and_then(|sha| config.commit_messages.get(sha)),
+                                "date": locale.date(&p.x, "%Y-%m-%d %H:%M:%S %Z"),
+                                "value": unit.map_or(value.clone(), |unit| format!("{} {}", value, unit)),
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    json!({ "params": params, "points": points })
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "name": config.group_aliases.resolve(group),
+                "desc": desc,
+                "divisor": divisor,
+                "lines": lines,
+            })
+        })
+        .collect::<Vec<_>>();
+    // `</` would end the script element the data is embedded in
+    let data = Value::Array(groups).to_string().replace("</", "<\\/");
+    TEMPLATE.replace("{{PLOT_DATA}}", &data)
+}
@@ -0,0 +1,80 @@
+use std::fmt::Write;
+
+#[cfg(feature = "http")]
+use anyhow::Context;
+#[cfg(feature = "http")]
+use reqwest::blocking::Client;
+
+use crate::plot::Plots;
+use crate::prometheus::{measured_group, value_name};
+
+// Measurement every point is written to, tagged with its group and params
+const MEASUREMENT: &str = "benchmark";
+
+// Every point in InfluxDB's line protocol, timestamped with its commit date in nanoseconds, e.g.
+// `benchmark,group=Fibonacci-num\=10,params=rc\=100 time_ns=2010000 1704067200000000000`. The field
+// is named by `value_name` like the Prometheus gauges. Lines without params get no `params` tag,
+// since tag values can't be empty
+pub fn line_protocol(plots: &Plots) -> String {
+    let mut text = String::new();
+    for (group, plot) in plots.0.iter() {
+        let (field, scale) = value_name(plot);
+        let group = escape(measured_group(group));
+        for (params, points) in plot.lines.iter() {
+            let mut tags = format!("{},group={}", MEASUREMENT, group);
+            if !params.is_empty() {
+                write!(tags, ",params={}", escape(params)).unwrap();
+            }
+            for point in points {
+                writeln!(
+                    text,
+                    "{} {}={} {}",
+                    tags,
+                    field,
+                    point.y * scale,
+                    point.x.timestamp_nanos()
+                )
+                .unwrap();
+            }
+        }
+    }
+    text
+}
+
+// Whether `--influx` was given a URL to push to rather than a file to write
+pub fn is_url(target: &str) -> bool {
+    target.starts_with("http://") || target.starts_with("https://")
+}
+
+// Posts the line protocol `body` to an InfluxDB write endpoint, e.g.
+// `http://localhost:8086/api/v2/write?org=lurk&bucket=benchmarks`, whose default precision is
+// nanoseconds. The `token` is needed by InfluxDB 2, and sent as `Authorization: Token <token>`
+#[cfg(feature = "http")]
+pub fn push(url: &str, body: String, token: Option<&str>) -> anyhow::Result<()> {
+    let mut request = Client::new().post(url).body(body);
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Token {}", token));
+    }
+    request
+        .send()?
+        .error_for_status()
+        .context("InfluxDB rejected the points")?;
+    Ok(())
+}
+
+// Escapes the commas, equals signs and spaces that would otherwise end a tag value, and replaces
+// newlines and other control characters, which can't be escaped, with `_`
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            ',' | '=' | ' ' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_control() => escaped.push('_'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
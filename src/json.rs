@@ -1,67 +1,472 @@
 use core::fmt;
-use std::io::Read;
-use std::{fs::File, path::Path};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
 
-use serde::Deserialize;
+use anyhow::Context;
+use camino::Utf8Path;
+use flate2::read::MultiGzDecoder;
+use serde::{Deserialize, Serialize};
 use serde_json::de::{StrRead, StreamDeserializer};
-use serde_json::{Deserializer, Error, Value};
+use serde_json::{Deserializer, Error, Map, Value};
+use tracing::warn;
 
-#[derive(Debug, Deserialize)]
+use crate::config::{BenchIdFormat, IdSegment, Metric};
+use crate::plot::{ns_per, PlotKind};
+
+#[derive(Debug)]
 pub struct BenchData {
     pub id: BenchId,
-    #[serde(rename = "typical")]
     pub result: BenchResult,
+    // Measurement unit of the record, e.g. `ns`, or a custom measurement's unit
+    pub unit: Option<String>,
+    pub kind: PlotKind,
 }
 
-#[derive(Debug)]
+impl BenchData {
+    // The record's unit, falling back to the one given in the estimate itself
+    pub fn unit(&self) -> Option<&str> {
+        self.unit.as_deref().or(self.result.unit.as_deref())
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct BenchId {
     pub group_name: String,
     pub bench_name: String,
     pub params: String,
 }
 
-// Assumes three `String` elements in a Criterion bench ID: <group>/<name>/<params>
+// Parses a bench ID in the given format, by default Criterion's <group>/<name>/<params>
 // E.g. `Fibonacci-num=10/28db40f-2024-01-30T19:07:04-05:00/rc=100`
-// Errors if a different format is found
-impl<'de> Deserialize<'de> for BenchId {
+// Any further segments are kept in the last part, e.g. params `rc=100/extra=foo`, and IDs without
+// params get empty ones. Errors if there's no name to read the commit from
+impl BenchId {
+    pub fn parse(s: &str, format: &BenchIdFormat) -> Result<Self, String> {
+        let mut id = s.splitn(format.segments.len(), format.separator.as_str());
+        let (mut group_name, mut bench_name, mut params) = (None, None, None);
+        for segment in &format.segments {
+            let part = id.next();
+            match segment {
+                IdSegment::Group => group_name = part,
+                IdSegment::Bench => bench_name = part,
+                IdSegment::Params => params = part,
+            }
+        }
+        match (group_name, bench_name) {
+            (Some(group_name), Some(bench_name)) if !group_name.is_empty() => Ok(BenchId {
+                group_name: group_name.to_owned(),
+                // Criterion converts `:` to `_` in the timestamp as the former is valid JSON
+                // syntax, so we convert `_` back to `:` when parsing
+                bench_name: if format.restore_colons {
+                    bench_name.replace('_', ":")
+                } else {
+                    bench_name.to_owned()
+                },
+                params: params.unwrap_or_default().to_owned(),
+            }),
+            _ => Err(format!(
+                "Expected a bench ID `{}`, found `{}`",
+                id_pattern(format),
+                s
+            )),
+        }
+    }
+}
+
+// The shape of an ID in `format`, e.g. `<group>/<name>[/<params>]`
+fn id_pattern(format: &BenchIdFormat) -> String {
+    let mut pattern = String::new();
+    for (i, segment) in format.segments.iter().enumerate() {
+        let sep = if i == 0 {
+            ""
+        } else {
+            format.separator.as_str()
+        };
+        match segment {
+            IdSegment::Group => pattern += &format!("{sep}<group>"),
+            IdSegment::Bench => pattern += &format!("{sep}<name>"),
+            // Only trailing params can be left out
+            IdSegment::Params if i + 1 == format.segments.len() => {
+                pattern += &format!("[{sep}<params>]")
+            }
+            IdSegment::Params => pattern += &format!("{sep}<params>"),
+        }
+    }
+    pattern
+}
+
+#[derive(Debug)]
+pub struct BenchResult {
+    pub time: f64,
+    pub interval: Option<Interval>,
+    pub unit: Option<String>,
+}
+
+// Confidence interval around a benchmark estimate
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Interval {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+// JSON pointers to the (lower, upper) confidence bounds of an estimate in each Criterion version
+const INTERVAL_FIELDS: [(&str, &str); 3] = [
+    ("/lower_bound", "/upper_bound"),
+    (
+        "/confidence_interval/lower_bound",
+        "/confidence_interval/upper_bound",
+    ),
+    ("/lower", "/upper"),
+];
+
+#[derive(Deserialize)]
+struct RawEstimate {
+    #[serde(deserialize_with = "deserialize_number")]
+    estimate: f64,
+    #[serde(flatten)]
+    fields: Map<String, Value>,
+}
+
+// Accepts a JSON integer, float, or numeric string such as `"1234.5"`, since not every harness
+// emits the estimate as a float
+fn deserialize_number<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Number(n) => n
+            .as_f64()
+            .ok_or_else(|| serde::de::Error::custom("Number out of `f64` range")),
+        Value::String(s) => s
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| serde::de::Error::custom(format!("Invalid numeric string `{s}`: {e}"))),
+        other => Err(serde::de::Error::custom(format!(
+            "Expected a number or numeric string, found {other}"
+        ))),
+    }
+}
+
+// The interval is taken from the first known field names present, and omitted if there are none
+impl<'de> Deserialize<'de> for BenchResult {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        let s = String::deserialize(deserializer)?;
-        let id = s.split('/').collect::<Vec<&str>>();
-        if id.len() != 3 {
-            Err(serde::de::Error::custom("Expected 3 bench ID elements"))
-        } else {
-            let bench_name = id[1].replace('_', ":");
-            Ok(BenchId {
-                group_name: id[0].to_owned(),
-                // Criterion converts `:` to `_` in the timestamp as the former is valid JSON syntax,
-                // so we convert `_` back to `:` when deserializing
-                bench_name,
-                params: id[2].to_owned(),
+        let raw = RawEstimate::deserialize(deserializer)?;
+        let fields = Value::Object(raw.fields);
+        let interval = INTERVAL_FIELDS.iter().find_map(|(lower, upper)| {
+            Some(Interval {
+                lower: fields.pointer(lower)?.as_f64()?,
+                upper: fields.pointer(upper)?.as_f64()?,
             })
+        });
+        let unit = fields
+            .get("unit")
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+        Ok(BenchResult {
+            time: raw.estimate,
+            interval,
+            unit,
+        })
+    }
+}
+
+// Selects which value of each JSON record is plotted
+#[derive(Debug, Clone)]
+pub enum Extractor {
+    // The `estimate` time of one of Criterion's estimates, e.g. `typical`
+    Time(Metric),
+    // An integer metric such as a constraint count, found at the given JSON pointer e.g. `/constraints`
+    Count(String),
+}
+
+impl Extractor {
+    fn plots_throughput(&self) -> bool {
+        matches!(self, Extractor::Time(Metric::Throughput))
+    }
+
+    // The value a record needs to be plotted, e.g. "a `typical` estimate"
+    pub fn expected(&self) -> String {
+        match self {
+            Extractor::Time(Metric::Throughput) => "a throughput".to_owned(),
+            Extractor::Time(metric) => format!("a `{}` estimate", metric.field()),
+            Extractor::Count(pointer) => format!("an integer at `{}`", pointer),
         }
     }
 }
 
+// A record with only the bench ID read, leaving the other fields for an `Extractor` to look up
 #[derive(Debug, Deserialize)]
-pub struct BenchResult {
-    #[serde(rename = "estimate")]
-    pub time: f64,
+struct RawRecord {
+    id: String,
+    #[serde(flatten)]
+    fields: Map<String, Value>,
+}
+
+// A `RawRecord` with its bench ID parsed
+struct Record {
+    id: BenchId,
+    fields: Map<String, Value>,
+}
+
+// Magic bytes at the start of every gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// Deserializes the benchmark JSON file into structured data for plotting, see `read_json_from_reader`
+// Gzipped files, e.g. archived `.json.gz` results, are decompressed first. They're recognized by
+// their magic bytes rather than the extension, so plain files are read as before whatever their name
+pub fn read_json_from_file<P: AsRef<Utf8Path>>(
+    path: P,
+    extractor: &Extractor,
+    id_format: &BenchIdFormat,
+) -> anyhow::Result<(Vec<BenchData>, Vec<JsonError>)> {
+    let path = path.as_ref();
+    let records = open(path)
+        .and_then(|file| read_records(file, extractor, id_format))
+        .with_context(|| format!("Failed to read {}", path))?;
+    if records.incomplete > 0 && extractor.plots_throughput() {
+        warn!(
+            "skipped {} results without throughput in {}",
+            records.incomplete, path
+        );
+    }
+    Ok((records.data, records.errors))
+}
+
+// Deserializes benchmark JSON, e.g. piped to stdin, into structured data for plotting. Errors only
+// if reading fails, since records that fail to parse are skipped and returned alongside the data
+pub fn read_json_from_reader<R: Read>(
+    reader: R,
+    extractor: &Extractor,
+    id_format: &BenchIdFormat,
+) -> anyhow::Result<(Vec<BenchData>, Vec<JsonError>)> {
+    let records = read_records(reader, extractor, id_format)?;
+    if records.incomplete > 0 && extractor.plots_throughput() {
+        warn!("skipped {} results without throughput", records.incomplete);
+    }
+    Ok((records.data, records.errors))
+}
+
+// How many of a file's records would be plotted, and why the others wouldn't be, for `--validate`
+pub struct Validation {
+    pub matched: usize,
+    pub errors: Vec<JsonError>,
+    // Records that parsed but lack the value being plotted, e.g. their `typical.estimate`
+    pub incomplete: usize,
+}
+
+impl Validation {
+    pub fn rejected(&self) -> usize {
+        self.errors.len() + self.incomplete
+    }
+
+    // Fraction of the records that were rejected. A file without any records has nothing to plot,
+    // so counts as entirely rejected
+    pub fn reject_rate(&self) -> f64 {
+        match self.matched + self.rejected() {
+            0 => 1.0,
+            total => self.rejected() as f64 / total as f64,
+        }
+    }
+}
+
+// Checks how many records of a bench file match the expected format, see `Validation`
+pub fn validate_file(
+    path: &Utf8Path,
+    extractor: &Extractor,
+    id_format: &BenchIdFormat,
+) -> anyhow::Result<Validation> {
+    open(path)
+        .and_then(|file| validate_reader(file, extractor, id_format))
+        .with_context(|| format!("Failed to read {}", path))
+}
+
+// Checks how many records of benchmark JSON, e.g. piped to stdin, match the expected format
+pub fn validate_reader<R: Read>(
+    reader: R,
+    extractor: &Extractor,
+    id_format: &BenchIdFormat,
+) -> anyhow::Result<Validation> {
+    let records = read_records(reader, extractor, id_format)?;
+    Ok(Validation {
+        matched: records.matched,
+        errors: records.errors,
+        incomplete: records.incomplete,
+    })
+}
+
+// Opens a bench file. Gzipped files, e.g. archived `.json.gz` results, are decompressed
+fn open(path: &Utf8Path) -> anyhow::Result<Box<dyn Read>> {
+    let file = File::open(path).context("Failed to open the file")?;
+    let mut file = BufReader::new(file);
+    if file.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(MultiGzDecoder::new(file)))
+    } else {
+        Ok(Box::new(file))
+    }
 }
 
-// Deserializes the benchmark JSON file into structured data for plotting
-pub fn read_json_from_file<P: AsRef<Path>>(path: P) -> Result<Vec<BenchData>, Error> {
-    let mut file = File::open(path).unwrap();
+// What `read_records` made of benchmark JSON
+struct Records {
+    data: Vec<BenchData>,
+    // Records with a value to plot. Their memory estimates are in `data` too
+    matched: usize,
+    // Records that failed to parse
+    errors: Vec<JsonError>,
+    // Records that parsed, but lack the value the extractor plots
+    incomplete: usize,
+}
+
+fn read_records<R: Read>(
+    mut reader: R,
+    extractor: &Extractor,
+    id_format: &BenchIdFormat,
+) -> anyhow::Result<Records> {
     let mut s = String::new();
-    file.read_to_string(&mut s).unwrap();
+    reader.read_to_string(&mut s)?;
 
     let mut data = vec![];
-    for result in ResilientStreamDeserializer::<BenchData>::new(&s).flatten() {
-        data.push(result);
+    let mut errors = vec![];
+    let mut incomplete = 0;
+    let records = ResilientStreamDeserializer::<RawRecord>::new(&s).filter_map(|record| {
+        let record = record.map_err(|e| errors.push(e)).ok()?;
+        match BenchId::parse(&record.id, id_format) {
+            Ok(id) => Some(Record {
+                id,
+                fields: record.fields,
+            }),
+            Err(e) => {
+                let mut value = record.fields;
+                value.insert("id".to_owned(), Value::String(record.id));
+                errors.push(JsonError {
+                    error: serde::de::Error::custom(e),
+                    value: Some(Value::Object(value)),
+                });
+                None
+            }
+        }
+    });
+    match extractor {
+        Extractor::Time(metric) => {
+            for record in records {
+                // Harnesses don't necessarily report every estimate, e.g. `slope` is null without
+                // linear sampling, so records missing the chosen one are skipped
+                let Some(Ok(result)) = record
+                    .fields
+                    .get(metric.field())
+                    .map(BenchResult::deserialize)
+                else {
+                    incomplete += 1;
+                    continue;
+                };
+                let unit = record
+                    .fields
+                    .get("unit")
+                    .and_then(Value::as_str)
+                    .map(str::to_owned);
+                let (result, unit, kind) = match metric {
+                    Metric::Throughput => {
+                        let Some((result, unit)) =
+                            throughput(&record.fields, result, unit.as_deref())
+                        else {
+                            incomplete += 1;
+                            continue;
+                        };
+                        (result, Some(unit.to_owned()), PlotKind::Throughput)
+                    }
+                    _ => (result, unit, PlotKind::Time),
+                };
+                // Harnesses that measure peak memory too report it as a `memory` estimate, e.g.
+                // `{"estimate": 1048576, "unit": "bytes"}`, which is plotted separately
+                if let Some(Ok(memory)) = record.fields.get("memory").map(BenchResult::deserialize)
+                {
+                    data.push(BenchData {
+                        id: record.id.clone(),
+                        result: memory,
+                        unit: None,
+                        kind: PlotKind::Memory,
+                    });
+                }
+                data.push(BenchData {
+                    id: record.id,
+                    result,
+                    unit,
+                    kind,
+                });
+            }
+        }
+        Extractor::Count(pointer) => {
+            for record in records {
+                // Records without an integer at the pointer are skipped like any other mismatch
+                let fields = Value::Object(record.fields);
+                if let Some(count) = fields.pointer(pointer).and_then(Value::as_u64) {
+                    data.push(BenchData {
+                        id: record.id,
+                        result: BenchResult {
+                            time: count as f64,
+                            interval: None,
+                            unit: None,
+                        },
+                        unit: None,
+                        kind: PlotKind::Count,
+                    });
+                } else {
+                    incomplete += 1;
+                }
+            }
+        }
     }
-    Ok(data)
+    // Every record that wasn't skipped has one plotted datum besides its memory
+    let matched = data.iter().filter(|d| d.kind != PlotKind::Memory).count();
+    Ok(Records {
+        data,
+        matched,
+        errors,
+        incomplete,
+    })
+}
+
+// Rate at which a record's iterations process their throughput, from cargo-criterion's
+// `[{"per_iteration": 1024, "unit": "bytes"}]` or Criterion's own `{"Bytes": 1024}`, and the unit
+// it's in. The interval's bounds swap, since the slowest time is the lowest rate. `None` if the
+// record has no throughput, or its time isn't in a known unit
+fn throughput(
+    fields: &Map<String, Value>,
+    time: BenchResult,
+    time_unit: Option<&str>,
+) -> Option<(BenchResult, &'static str)> {
+    let (per_iteration, unit) = match fields.get("throughput")? {
+        Value::Array(throughputs) => {
+            let throughput = throughputs.first()?;
+            (
+                throughput.get("per_iteration")?.as_f64()?,
+                throughput.get("unit")?.as_str()?,
+            )
+        }
+        Value::Object(throughput) => {
+            let (unit, per_iteration) = throughput.iter().next()?;
+            (per_iteration.as_f64()?, unit.as_str())
+        }
+        _ => return None,
+    };
+    let unit = match unit.to_lowercase().as_str() {
+        "bytes" | "bytesdecimal" => "B/s",
+        "elements" => "elem/s",
+        _ => return None,
+    };
+    let ns = ns_per(time_unit.or(time.unit.as_deref()).unwrap_or("ns"))?;
+    let rate = |time: f64| per_iteration * 1e9 / (time * ns);
+    let result = BenchResult {
+        time: rate(time.time),
+        interval: time.interval.map(|interval| Interval {
+            lower: rate(interval.upper),
+            upper: rate(interval.lower),
+        }),
+        unit: None,
+    };
+    Some((result, unit))
 }
 
 // The following code is taken from https://users.rust-lang.org/t/step-past-errors-in-serde-json-streamdeserializer/84228/10
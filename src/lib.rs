@@ -0,0 +1,30 @@
+// The plotting pipeline behind the `benchmark-plotter` binary, for tools that embed it: `run` does
+// everything the binary does for a `Config`, while `Plots` and `read_json_from_reader` can be used
+// to read Criterion results and build up plot data directly
+mod analysis;
+mod badge;
+pub mod cli;
+pub mod config;
+mod csv;
+mod git;
+#[cfg(feature = "http")]
+mod github;
+mod html;
+mod influx;
+mod json;
+pub mod locale;
+mod markdown;
+mod plot;
+mod prometheus;
+mod run;
+mod scaled;
+mod sink;
+#[cfg(feature = "http")]
+mod slack;
+mod vega;
+
+#[cfg(feature = "http")]
+pub use github::PullRequest;
+pub use json::{read_json_from_reader, BenchData, Extractor, JsonError};
+pub use plot::Plots;
+pub use run::{run, RunOptions, RunSummary};
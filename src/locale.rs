@@ -0,0 +1,147 @@
+use std::{fmt, str::FromStr, sync::OnceLock};
+
+use anyhow::anyhow;
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+// How dates and numbers are written on chart axes and labels and in the printed reports. The
+// defaults keep the existing en-US style output, e.g. `2024-01-31` and `1234567.5`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Locale {
+    // `strftime` format for every date, e.g. `%d.%m.%Y`. Unset keeps each output's own format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    // Zone dates are shown in. They're stored and compared in UTC either way
+    pub timezone: Timezone,
+    pub decimal_separator: String,
+    // Inserted between each group of three integer digits, e.g. `.` for `1.234.567,5`
+    pub thousands_separator: String,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            date_format: None,
+            timezone: Timezone::Utc,
+            decimal_separator: ".".into(),
+            thousands_separator: String::new(),
+        }
+    }
+}
+
+// Zone dates are shown in: `utc`, the machine's `local` zone, or a fixed offset like `-05:00`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Timezone {
+    #[default]
+    Utc,
+    Local,
+    Offset(FixedOffset),
+}
+
+// Parses `utc`, `local`, or an offset `+HH:MM`, `-HH:MM` or `+HH`
+impl FromStr for Timezone {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "utc" | "UTC" => return Ok(Timezone::Utc),
+            "local" => return Ok(Timezone::Local),
+            _ => {}
+        }
+        let invalid = || anyhow!("Expected `utc`, `local` or an offset like `-05:00`, got `{s}`");
+        let sign = match s.chars().next() {
+            Some('+') => 1,
+            Some('-') => -1,
+            _ => return Err(invalid()),
+        };
+        let (hours, minutes) = s[1..].split_once(':').unwrap_or((&s[1..], "00"));
+        let (hours, minutes) = match (hours.parse::<i32>(), minutes.parse::<i32>()) {
+            (Ok(h), Ok(m)) if hours.len() == 2 && minutes.len() == 2 && m < 60 => (h, m),
+            _ => return Err(invalid()),
+        };
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+            .map(Timezone::Offset)
+            .ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for Timezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Timezone::Utc => write!(f, "utc"),
+            Timezone::Local => write!(f, "local"),
+            Timezone::Offset(offset) => write!(f, "{offset}"),
+        }
+    }
+}
+
+// Config files store the zone in the same form as the command line
+impl From<Timezone> for String {
+    fn from(timezone: Timezone) -> Self {
+        timezone.to_string()
+    }
+}
+
+impl TryFrom<String> for Timezone {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+static CURRENT: OnceLock<Locale> = OnceLock::new();
+
+// Sets the locale used for the rest of the run. Only the first call has an effect
+pub fn set(locale: Locale) {
+    let _ = CURRENT.set(locale);
+}
+
+// The locale set by `set`, or the default if it wasn't called
+pub fn current() -> &'static Locale {
+    CURRENT.get_or_init(Locale::default)
+}
+
+impl Locale {
+    // Formats `date` in `timezone` with `date_format`, or with `default` if it isn't set
+    pub fn date(&self, date: &DateTime<Utc>, default: &str) -> String {
+        let format = self.date_format.as_deref().unwrap_or(default);
+        match self.timezone {
+            Timezone::Utc => date.format(format).to_string(),
+            Timezone::Local => date.with_timezone(&Local).format(format).to_string(),
+            Timezone::Offset(offset) => date.with_timezone(&offset).format(format).to_string(),
+        }
+    }
+
+    // Replaces the separators of a number already formatted in the default style, e.g. by
+    // plotters or with `{:.2}`. Anything that isn't a plain decimal (`NaN`, `1e-7`) is kept as-is
+    pub fn number(&self, formatted: &str) -> String {
+        let (sign, digits) = formatted.split_at(formatted.starts_with(['-', '+']) as usize);
+        let (int, frac) = digits
+            .split_once('.')
+            .map_or((digits, None), |(int, frac)| (int, Some(frac)));
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if int.is_empty() || !is_digits(int) || !frac.map_or(true, is_digits) {
+            return formatted.to_owned();
+        }
+        let mut out = sign.to_owned();
+        for (i, c) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                out.push_str(&self.thousands_separator);
+            }
+            out.push(c);
+        }
+        if let Some(frac) = frac {
+            out.push_str(&self.decimal_separator);
+            out.push_str(frac);
+        }
+        out
+    }
+
+    // Formats `value` with a fixed number of decimals
+    pub fn fixed(&self, value: f64, decimals: usize) -> String {
+        self.number(&format!("{:.*}", decimals, value))
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt::Write;
+
+use crate::analysis::Comparison;
+use crate::locale;
+use crate::plot::{Plot, Plots};
+
+// Markdown table comparing the latest result of every params line to the previous one, for
+// posting on PRs. Values are shown in the same unit as the plot's Y axis, e.g. `2.01 ms`, and
+// lines with a single point show `—` for the change
+pub fn comparison_table(plots: &Plots) -> String {
+    let mut table = String::from(
+        "| Benchmark | Params | Previous | Latest | Change |\n\
+         |-----------|--------|---------:|-------:|-------:|\n",
+    );
+    for (group, plot) in plots.0.iter() {
+        let value = formatter(plot);
+        for (params, points) in plot.lines.iter() {
+            let Some(latest) = points.last() else {
+                continue;
+            };
+            let (previous, change) = match points.len().checked_sub(2).map(|i| &points[i]) {
+                Some(previous) => {
+                    let change = (latest.y / previous.y - 1.0) * 100.0;
+                    (value(previous.y), change_cell(change))
+                }
+                None => ("—".to_owned(), "—".to_owned()),
+            };
+            writeln!(
+                table,
+                "| {} | {} | {} | {} | {} |",
+                cell(group),
+                cell(params),
+                previous,
+                value(latest.y),
+                change
+            )
+            .unwrap();
+        }
+    }
+    table
+}
+
+// Markdown table of each line's value at the two commits of `--compare`, like `comparison_table`
+pub fn head_to_head_table(
+    plots: &Plots,
+    comparisons: &[Comparison],
+    base: &str,
+    head: &str,
+) -> String {
+    let mut table = format!(
+        "| Benchmark | Params | {} | {} | Change |\n\
+         |-----------|--------|---:|---:|-------:|\n",
+        cell(base),
+        cell(head)
+    );
+    for comparison in comparisons {
+        let value = formatter(&plots.0[comparison.group]);
+        writeln!(
+            table,
+            "| {} | {} | {} | {} | {} |",
+            cell(comparison.group),
+            cell(comparison.params),
+            value(comparison.base.y),
+            value(comparison.head.y),
+            change_cell(comparison.percent_change())
+        )
+        .unwrap();
+    }
+    table
+}
+
+// Formats values in the same unit as the plot's Y axis, e.g. `2.01 ms`
+fn formatter(plot: &Plot) -> impl Fn(f64) -> String + '_ {
+    let (unit, divisor) = match plot.display_unit() {
+        Some((unit, divisor)) => (Some(unit), divisor),
+        None => (plot.unit.as_deref(), 1.0),
+    };
+    let locale = locale::current();
+    move |y: f64| match unit {
+        Some(unit) => format!("{} {}", locale.fixed(y / divisor, 2), unit),
+        None => locale.fixed(y / divisor, 2),
+    }
+}
+
+// Percent change with an arrow for its direction, e.g. `▲ +7.5%`
+fn change_cell(change: f64) -> String {
+    let arrow = if change > 0.0 {
+        "▲"
+    } else if change < 0.0 {
+        "▼"
+    } else {
+        ""
+    };
+    let change = locale::current().number(&format!("{:+.1}", change));
+    format!("{arrow} {change}%").trim().to_owned()
+}
+
+// Escapes pipes, which would otherwise split the cell
+fn cell(s: &str) -> String {
+    s.replace('|', "\\|")
+}
@@ -1,100 +1,1863 @@
-use plotters::prelude::*;
+use image::{
+    codecs::png::PngEncoder,
+    imageops::{self, FilterType},
+    ColorType, ImageEncoder, RgbImage,
+};
+use plotters::{
+    coord::{ranged1d::ValueFormatter, types::RangedCoordf64, Shift},
+    prelude::*,
+    style::{
+        text_anchor::{HPos, Pos, VPos},
+        SizeDesc,
+    },
+};
 
-use chrono::{serde::ts_seconds, DateTime, Duration, Utc};
+use chrono::{serde::ts_seconds_option, DateTime, Duration, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
-use std::{collections::HashMap, error::Error};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    error::Error,
+    num::NonZeroUsize,
+};
 
-use crate::json::BenchData;
+use crate::analysis::{stability_ranking, step_change, Comparison, StepChange};
+use crate::config::{
+    CaptionAlign, CaptionConfig, CaptionOverflow, ChartConfig, ConflictPolicy, GeoMean,
+    GroupFilter, IngestConfig, LineColor, Metric, OutputFormat, Quality, RangeConfig,
+    RetentionConfig, Smoothing, SweepAt, Theme, XAxis,
+};
+use crate::json::{BenchData, Interval};
+use crate::locale::{self, Locale, Timezone};
+use crate::scaled::ScaledBackend;
+use crate::sink::ChartSink;
+use crate::vega::vega_spec;
 
-// TODO: Figure out how to include the commit hash as a label on the point or X-axis
-pub fn generate_plots(data: &Plots) -> Result<(), Box<dyn Error>> {
+// Renders every group's chart, and any extra images the config asks for, into `sink`. Returns
+// each group's chart file name, keyed by its group
+pub fn generate_plots(
+    data: &Plots,
+    config: &ChartConfig,
+    sink: &mut dyn ChartSink,
+) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    let warmed_up;
+    let data = if config.skip_warmup > 0 {
+        warmed_up = data.skip_warmup(config.skip_warmup);
+        &warmed_up
+    } else {
+        data
+    };
+    let (width, height) = config.size();
+    let noisiest = noisiest_lines(data, config.annotate_noisiest);
+    // Group name -> output filename, written as `manifest.json` when names are content-hashed
+    let mut manifest = BTreeMap::new();
     for plot in data.0.iter() {
-        let out_file_name = format!("./{}.png", plot.0);
-        let root = BitMapBackend::new(&out_file_name, (1024, 768)).into_drawing_area();
-        root.fill(&WHITE)?;
-
-        let mut chart = ChartBuilder::on(&root)
-            .margin(10)
-            .caption(plot.0, ("sans-serif", 40))
-            .set_label_area_size(LabelAreaPosition::Left, 60)
-            .set_label_area_size(LabelAreaPosition::Bottom, 40)
-            .build_cartesian_2d(
-                // Add one day buffer before and after
-                plot.1
-                    .x_axis
-                    .min
-                    .checked_sub_signed(Duration::days(1))
-                    .expect("DateTime underflow")
-                    ..plot
-                        .1
-                        .x_axis
-                        .max
-                        .checked_add_signed(Duration::days(1))
-                        .expect("DateTime overflow"),
-                // Add 0.2 ns buffer before and after (not rigorous, based on a priori knowledge of Y axis units & values)
-                plot.1.y_axis.min - 0.2f64..plot.1.y_axis.max + 0.2f64,
-            )?;
-
-        chart
-            .configure_mesh()
-            .disable_x_mesh()
-            .disable_y_mesh()
-            .x_labels(10)
-            .max_light_lines(4)
-            .x_desc("Commit Date")
-            .y_desc("Time (ns)")
-            .draw()?;
-
-        // Draws the lines of benchmark data points, one line/color per set of bench ID params e.g. `rc=100`
-        for (i, line) in plot.1.lines.iter().enumerate() {
-            // Draw lines between each point
-            chart
-                .draw_series(LineSeries::new(
-                    line.1.iter().map(|p| (p.x, p.y)),
-                    Palette99::pick(i),
-                ))?
-                .label(line.0)
-                // TODO: Move the legend out of the plot area
-                .legend(move |(x, y)| {
-                    Rectangle::new(
-                        [(x - 5, y - 5), (x + 5, y + 5)],
-                        Palette99::pick(i).filled(),
-                    )
-                });
+        let name = &*plot_name(config, plot.0);
+        // A group can be left without points, e.g. by hand-edited plot data, and has no X range
+        if plot.1.x_axis.bounds().is_none() {
+            warn!("skipping {}, which has no points", name);
+            continue;
+        }
+        let chart = if config.format == OutputFormat::Vega {
+            let range = config.range_for(plot.0);
+            serde_json::to_vec_pretty(&vega_spec(name, plot.1, &range, (width, height)))?
+        } else {
+            render_chart!(config, width, height, |root| {
+                draw_group(root, plot.0, plot.1, config, &noisiest, true)
+            })?
+        };
 
-            // Draw dots on each point
-            chart.draw_series(
-                line.1
-                    .iter()
-                    .map(|p| Circle::new((p.x, p.y), 3, Palette99::pick(i).filled())),
-            )?;
-            chart
-                .configure_series_labels()
-                .background_style(WHITE)
-                .border_style(BLACK)
-                .draw()?;
+        let hash = config.hashed_names.then(|| content_hash(&chart) as u32);
+        let file_name = chart_file_name(config, plot.0, name, plot.1, hash);
+        sink.write_chart(&file_name, &chart)?;
+        manifest.insert(plot.0.clone(), file_name);
+
+        if config.format == OutputFormat::Vega {
+            continue;
+        }
+        if let Some(profile) = config.sweep_at.and_then(|at| sweep_profile(plot.1, at)) {
+            let chart = render_chart!(config, width, height, |root| {
+                draw_sweep(root, name, plot.1, &profile, config)
+            })?;
+            let file_name =
+                chart_file_name(config, plot.0, &format!("{}.sweep", name), plot.1, None);
+            sink.write_chart(&file_name, &chart)?;
+        }
+    }
+
+    if config.hashed_names {
+        sink.write_chart("manifest.json", &serde_json::to_vec_pretty(&manifest)?)?;
+    }
+
+    if let Some(file_name) = config
+        .combined
+        .as_ref()
+        .filter(|_| config.format != OutputFormat::Vega)
+    {
+        let chart = render_combined(data, config, &noisiest, width, height)?;
+        sink.write_chart(file_name, &chart)?;
+    }
+
+    if let Some(file_name) = config
+        .dashboard
+        .as_ref()
+        .filter(|_| config.format != OutputFormat::Vega)
+    {
+        let chart = render_dashboard(data, config, &noisiest, width, height)?;
+        sink.write_chart(file_name, &chart)?;
+    }
+
+    Ok(manifest)
+}
+
+// Expands the `output_stem` template for a chart, defaulting to `{group}.{format}`
+// `{date}` is the group's latest commit date and `{sha}` the short SHA of the commit being
+// plotted. The extension is appended if the template has no `{format}`, and a content hash, if
+// given, is inserted before the extension. The name is prefixed by the group's output
+// subdirectory, matched against the raw group name
+fn chart_file_name(
+    config: &ChartConfig,
+    raw_group: &str,
+    group: &str,
+    plot: &Plot,
+    hash: Option<u32>,
+) -> String {
+    let template = config.output_stem.as_deref().unwrap_or("{group}");
+    let mut name = template
+        .replace("{group}", group)
+        .replace(
+            "{date}",
+            &plot
+                .x_axis
+                .bounds()
+                .map_or(String::new(), |(_, max)| max.format("%Y-%m-%d").to_string()),
+        )
+        .replace("{sha}", &config.commit_sha)
+        .replace("{format}", config.format.extension());
+    if !template.contains("{format}") {
+        name = format!("{}.{}", name, config.format.extension());
+    }
+    if let Some(hash) = hash {
+        let extension = format!(".{}", config.format.extension());
+        name = match name.strip_suffix(&extension) {
+            Some(stem) => format!("{}.{:08x}{}", stem, hash, extension),
+            None => format!("{}.{:08x}", name, hash),
+        };
+    }
+    match config.output_dir_for(raw_group) {
+        Some(dir) => format!("{}/{}", dir.trim_end_matches('/'), name),
+        None => name,
+    }
+}
+
+// Renders the chart drawn by `$draw` on the drawing area `$root` in `config.format`. A macro
+// rather than a function because the drawing code is generic over the backend, which a closure
+// can't be, so the body is expanded once per backend
+macro_rules! render_chart {
+    ($config:expr, $width:expr, $height:expr, |$root:ident| $draw:expr $(,)?) => {
+        match $config.format {
+            OutputFormat::Svg => render_svg($width, $height, $config.scale(), |$root| $draw),
+            _ => render_png($width, $height, $config.scale(), $config.quality, |$root| {
+                $draw
+            }),
         }
+    };
+}
+use render_chart;
+
+// Renders into an SVG document, ready to pass to a sink for storage. Like `render_png`, the chart
+// is laid out at `width` x `height` and `scale` multiplies the output size
+fn render_svg<F>(width: u32, height: u32, scale: u32, draw: F) -> Result<Vec<u8>, Box<dyn Error>>
+where
+    F: FnOnce(&DrawingArea<ScaledBackend<SVGBackend>, Shift>) -> Result<(), Box<dyn Error>>,
+{
+    let mut svg = String::new();
+    {
+        let backend = SVGBackend::with_string(&mut svg, (width * scale, height * scale));
+        let root = ScaledBackend::new(backend, scale).into_drawing_area();
+        draw(&root)?;
+        root.present()?;
+    }
+    Ok(svg.into_bytes())
+}
+
+// Renders into an in-memory RGB buffer and encodes it as PNG, ready to pass to a sink for storage
+// The chart is laid out at `width` x `height` and written at `scale` times that size, so margins,
+// fonts and strokes grow with it, e.g. for HiDPI displays. At high quality the chart is drawn at
+// `SUPERSAMPLE` times the output size and downsampled, which antialiases lines and text since
+// plotters' bitmap rasterizer doesn't
+fn render_png<F>(
+    width: u32,
+    height: u32,
+    scale: u32,
+    quality: Quality,
+    draw: F,
+) -> Result<Vec<u8>, Box<dyn Error>>
+where
+    F: FnOnce(&DrawingArea<ScaledBackend<BitMapBackend>, Shift>) -> Result<(), Box<dyn Error>>,
+{
+    let supersample = match quality {
+        Quality::Standard => 1,
+        Quality::High => SUPERSAMPLE,
+    };
+    let (width, height) = (width * scale, height * scale);
+    let (buf_w, buf_h) = (width * supersample, height * supersample);
+    let mut buffer = vec![0u8; (buf_w * buf_h * 3) as usize];
+    {
+        let backend = BitMapBackend::with_buffer(&mut buffer, (buf_w, buf_h));
+        let root = ScaledBackend::new(backend, scale * supersample).into_drawing_area();
+        draw(&root)?;
+        root.present()?;
+    }
+    if supersample > 1 {
+        let image = RgbImage::from_raw(buf_w, buf_h, buffer).ok_or("Invalid bitmap buffer size")?;
+        buffer = imageops::resize(&image, width, height, FilterType::Triangle).into_raw();
+    }
+    let mut png = vec![];
+    PngEncoder::new(&mut png).write_image(&buffer, width, height, ColorType::Rgb8)?;
+    Ok(png)
+}
+
+// Factor by which `Quality::High` charts are oversized before downsampling
+const SUPERSAMPLE: u32 = 3;
+
+// Appended to a group's name for the key of its memory plot, and so its chart's file name, e.g.
+// `Fibonacci-num=10.memory.png` next to the time chart `Fibonacci-num=10.png`
+pub(crate) const MEMORY_SUFFIX: &str = ".memory";
+
+// Name a plot is shown and saved under: its group's alias, keeping the suffix of memory plots
+fn plot_name<'a>(config: &'a ChartConfig, key: &'a str) -> Cow<'a, str> {
+    match key.strip_suffix(MEMORY_SUFFIX) {
+        Some(group) => format!("{}{}", config.group_aliases.resolve(group), MEMORY_SUFFIX).into(),
+        None => config.group_aliases.resolve(key).into(),
+    }
+}
+
+// Draws a group as a heatmap or line chart depending on the config
+fn draw_group<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    group: &str,
+    plot: &Plot,
+    config: &ChartConfig,
+    noisiest: &NoisiestLines,
+    legend: bool,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let name = &*plot_name(config, group);
+    if config.heatmap_groups.iter().any(|g| g == group) {
+        draw_heatmap(root, name, plot, config)
+    } else {
+        let options = ChartOptions {
+            range: config.range_for(group),
+            target: config.targets.get(group).copied(),
+            baselines: baselines_for(group, plot, config),
+            noisy: noisiest
+                .iter()
+                .filter(|((g, _), _)| *g == group)
+                .map(|((_, params), cv)| (*params, *cv))
+                .collect(),
+            legend,
+        };
+        draw_chart(root, name, plot, config, &options)
+    }
+}
+
+// The lines annotated as the noisiest, keyed by (group, params), with their coefficient of
+// variation
+type NoisiestLines<'a> = HashMap<(&'a str, &'a str), f64>;
+
+// The `n` lines with the highest coefficient of variation across all groups
+fn noisiest_lines(data: &Plots, n: usize) -> NoisiestLines {
+    stability_ranking(data)
+        .into_iter()
+        .take(n)
+        .map(|line| ((line.group, line.params), line.cv))
+        .collect()
+}
+
+// Fraction of the data's range added on either side of each line chart axis
+const AXIS_MARGIN: f64 = 0.05;
+
+// Settings for one line chart, resolved from the config for its group
+struct ChartOptions<'a> {
+    range: RangeConfig,
+    target: Option<f64>,
+    // Baselines in the plot's stored units, with the params they apply to, or `None` for the
+    // whole group
+    baselines: Vec<(Option<&'a str>, f64)>,
+    // Params among the noisiest lines, with their coefficient of variation
+    noisy: HashMap<&'a str, f64>,
+    legend: bool,
+}
+
+impl ChartOptions<'_> {
+    // The baseline `params`' points are held to: their own, or else the group's
+    fn baseline_for(&self, params: &str) -> Option<f64> {
+        let find = |wanted: Option<&str>| {
+            self.baselines
+                .iter()
+                .find(|(params, _)| *params == wanted)
+                .map(|(_, value)| *value)
+        };
+        find(Some(params)).or_else(|| find(None))
+    }
+}
+
+// The configured baselines of `group`, converted to the units `plot` stores. Baselines whose unit
+// doesn't convert, e.g. `ms` on a memory plot, are skipped with a warning
+fn baselines_for<'a>(
+    group: &str,
+    plot: &Plot,
+    config: &'a ChartConfig,
+) -> Vec<(Option<&'a str>, f64)> {
+    let mut baselines = config
+        .baselines
+        .iter()
+        .filter_map(|(key, baseline)| {
+            // Group names can't contain `/`, while params can
+            let params = match key.split_once('/') {
+                Some((g, params)) if g == group => Some(params),
+                None if key == group => None,
+                _ => return None,
+            };
+            let value = match baseline.unit.as_deref() {
+                None => Some(baseline.value),
+                Some(unit) if Some(unit) == plot.unit.as_deref() => Some(baseline.value),
+                Some(unit) => {
+                    let stored = match plot.kind {
+                        PlotKind::Memory => plot.unit.as_deref().unwrap_or("B"),
+                        PlotKind::Time => plot.unit.as_deref().unwrap_or("ns"),
+                        PlotKind::Count | PlotKind::Throughput => "",
+                    };
+                    unit_scale(plot.kind, unit, stored).map(|scale| baseline.value * scale)
+                }
+            };
+            if value.is_none() {
+                warn!(
+                    "skipping the baseline of {}: `{}` doesn't convert to the plot's unit",
+                    key, baseline
+                );
+            }
+            Some((params, value?))
+        })
+        .collect::<Vec<_>>();
+    // Sorted, so overlapping labels are drawn in the same order on every run
+    baselines.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    baselines
+}
+
+// Stacks every group's chart vertically in a single image. If `shared_legend` is set and all groups
+// have the same params, their colors match and one legend is drawn in a reserved area on the right
+fn render_combined(
+    data: &Plots,
+    config: &ChartConfig,
+    noisiest: &NoisiestLines,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let groups = data
+        .0
+        .iter()
+        .filter(|(_, plot)| plot.x_axis.bounds().is_some())
+        .collect::<Vec<_>>();
+    let shared_params = if config.shared_legend {
+        shared_params(&groups)
+    } else {
+        None
+    };
+    let legend_width = shared_params.as_ref().map_or(0, |params| {
+        let longest = params.iter().map(|p| p.chars().count()).max().unwrap_or(0);
+        // Swatch, padding, and roughly 7px per char of the 12px legend font
+        40 + 7 * longest as u32
+    });
+
+    let colors = ThemeColors::of(config.theme);
+    render_chart!(
+        config,
+        width + legend_width,
+        height * groups.len().max(1) as u32,
+        |root| {
+            root.fill(&colors.background)?;
+            let (charts, legend_area) = root.split_horizontally(width);
+            let cells = charts.split_evenly((groups.len(), 1));
+            for (area, (group, plot)) in cells.iter().zip(groups.iter()) {
+                draw_group(area, group, plot, config, noisiest, shared_params.is_none())?;
+            }
+            if let Some(params) = &shared_params {
+                draw_shared_legend(&legend_area, params, config, &colors)?;
+            }
+            Ok(())
+        },
+    )
+}
+
+// Tiles every group's chart into a grid with about as many columns as rows, filled row by row.
+// Each cell is half the size of a standalone chart, so the overview stays a manageable size
+fn render_dashboard(
+    data: &Plots,
+    config: &ChartConfig,
+    noisiest: &NoisiestLines,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let groups = data
+        .0
+        .iter()
+        .filter(|(_, plot)| plot.x_axis.bounds().is_some())
+        .collect::<Vec<_>>();
+    let (rows, cols) = grid_size(groups.len());
+    let (cell_width, cell_height) = (width / 2, height / 2);
+
+    let colors = ThemeColors::of(config.theme);
+    render_chart!(
+        config,
+        cell_width * cols as u32,
+        cell_height * rows as u32,
+        |root| {
+            root.fill(&colors.background)?;
+            let cells = root.split_evenly((rows, cols));
+            for (area, (group, plot)) in cells.iter().zip(groups.iter()) {
+                draw_group(area, group, plot, config, noisiest, true)?;
+            }
+            Ok(())
+        },
+    )
+}
+
+// Rows and columns of the smallest roughly square grid with `n` cells, at least 1x1
+fn grid_size(n: usize) -> (usize, usize) {
+    let cols = (1..).find(|cols| cols * cols >= n).unwrap_or(1).max(1);
+    let rows = n.div_ceil(cols).max(1);
+    (rows, cols)
+}
+
+// Sorted params shared by every group drawn, or `None` if any group's params differ
+fn shared_params<'a>(groups: &[(&String, &'a Plot)]) -> Option<Vec<&'a str>> {
+    let mut param_sets = groups
+        .iter()
+        .map(|(_, plot)| plot.lines.keys().map(String::as_str).collect::<Vec<_>>());
+    let first = param_sets.next()?;
+    param_sets.all(|params| params == first).then_some(first)
+}
+
+// Draws a legend entry per params, using the same color assignment as `draw_chart`
+fn draw_shared_legend<DB: DrawingBackend>(
+    area: &DrawingArea<DB, Shift>,
+    params: &[&str],
+    config: &ChartConfig,
+    colors: &ThemeColors,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let style = TextStyle::from(("sans-serif", 12).into_font())
+        .color(&colors.foreground)
+        .pos(Pos::new(HPos::Left, VPos::Center));
+    let line_colors = line_colors(params, config, colors);
+    for (i, params) in params.iter().enumerate() {
+        let y = 60 + 20 * i as i32;
+        area.draw(&Rectangle::new(
+            [(10, y - 5), (20, y + 5)],
+            line_colors[i].filled(),
+        ))?;
+        area.draw_text(params, &style, (28, y))?;
+    }
+    Ok(())
+}
+
+// 64-bit FNV-1a hash of the chart contents. Unlike `DefaultHasher` this is stable across Rust
+// versions, so an unchanged chart keeps its cache-busting filename between runs
+fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// Colors of a chart's background and of its text, axes and borders, set by `ChartConfig::theme`
+struct ThemeColors {
+    theme: Theme,
+    background: RGBColor,
+    foreground: RGBColor,
+}
+
+impl ThemeColors {
+    fn of(theme: Theme) -> Self {
+        let (background, foreground) = match theme {
+            Theme::Light => (WHITE, BLACK),
+            Theme::Dark => (RGBColor(30, 30, 30), RGBColor(220, 220, 220)),
+        };
+        ThemeColors {
+            theme,
+            background,
+            foreground,
+        }
+    }
+
+    // The `i`th `Palette99` color. `Palette99` is made for white backgrounds and includes navy,
+    // maroon and black, so on a dark background every color is lightened by the same amount,
+    // which keeps them as distinguishable as on a light one
+    fn series(&self, i: usize) -> RGBColor {
+        let (r, g, b) = Palette99::pick(i).rgb();
+        match self.theme {
+            Theme::Light => RGBColor(r, g, b),
+            Theme::Dark => {
+                let lighten = |c: u8| c + ((255 - c) as u32 * 2 / 5) as u8;
+                RGBColor(lighten(r), lighten(g), lighten(b))
+            }
+        }
+    }
+
+    // `color` halfway to the background, e.g. for an overlay that shouldn't hide the line under it
+    fn faded(&self, color: RGBColor) -> RGBColor {
+        let fade = |c: u8, bg: u8| ((c as u16 + bg as u16) / 2) as u8;
+        let bg = self.background;
+        RGBColor(
+            fade(color.0, bg.0),
+            fade(color.1, bg.1),
+            fade(color.2, bg.2),
+        )
+    }
+}
+
+// Color of each of the sorted `params`' lines. Configured colors are used as-is, and other lines
+// get the `Palette99` color picked by a hash of their params, so a line keeps its color when lines
+// are added or removed around it. If that color is already taken in the chart, the next free one
+// is used
+fn line_colors(params: &[&str], config: &ChartConfig, colors: &ThemeColors) -> Vec<RGBColor> {
+    let n = Palette99::COLORS.len();
+    let mut taken = vec![false; n];
+    params
+        .iter()
+        .map(|params| {
+            if let Some(&LineColor(r, g, b)) = config.line_colors.get(*params) {
+                return RGBColor(r, g, b);
+            }
+            let hashed = content_hash(params.as_bytes()) as usize % n;
+            let slot = (0..n)
+                .map(|k| (hashed + k) % n)
+                .find(|&slot| !taken[slot])
+                .unwrap_or(hashed);
+            taken[slot] = true;
+            colors.series(slot)
+        })
+        .collect()
+}
+
+// Longest commit subject shown beside a highlighted change, beyond which it's cut short
+const SUBJECT_CHARS: usize = 40;
+
+const REGRESSION_COLOR: RGBColor = RGBColor(213, 94, 0);
+const IMPROVEMENT_COLOR: RGBColor = RGBColor(0, 158, 115);
+
+// Triangle centered on `(x, y)`, pointing up for a regression (the value rose) and down for an
+// improvement
+fn triangle(change: StepChange, (x, y): (i32, i32)) -> Vec<(i32, i32)> {
+    let dir = match change {
+        StepChange::Regression => -1,
+        StepChange::Improvement => 1,
+    };
+    vec![(x, y + 7 * dir), (x - 6, y - 4 * dir), (x + 6, y - 4 * dir)]
+}
+
+// Space between the plotting area and the legend
+const LEGEND_GAP: i32 = 10;
 
-        // To avoid the IO failure being ignored silently, we manually call the present function
-        root.present().expect("Unable to write result to file");
-        println!("Result has been saved to {}", out_file_name);
+// Entry of a line chart's legend, drawn by `draw_legend`
+struct LegendEntry {
+    label: String,
+    glyph: Glyph,
+}
+
+// Sample of a legend entry's series, drawn before its label
+enum Glyph {
+    // Square in a params line's color
+    Swatch(RGBAColor),
+    // Short line segment in the series' line style
+    Line(ShapeStyle),
+    // Change marker
+    Triangle(StepChange, RGBColor),
+}
+
+const LEGEND_FONT: (&str, u32) = ("sans-serif", 12);
+const LEGEND_ROW: i32 = 20;
+
+// Width of the area right of a line chart that fits its legend, including the gap to the plot
+fn legend_width<DB: DrawingBackend>(
+    area: &DrawingArea<DB, Shift>,
+    legend: &[LegendEntry],
+) -> Result<u32, Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    if legend.is_empty() {
+        return Ok(0);
+    }
+    let font = TextStyle::from(LEGEND_FONT.into_font());
+    let mut longest = 0;
+    for entry in legend {
+        longest = longest.max(area.estimate_text_size(&entry.label, &font)?.0);
     }
+    // Glyph column and padding on either side
+    Ok(LEGEND_GAP as u32 + 40 + longest)
+}
 
+// Draws a boxed legend with one row per entry at the top of `area`, after a gap to the plot
+fn draw_legend<DB: DrawingBackend>(
+    area: &DrawingArea<DB, Shift>,
+    legend: &[LegendEntry],
+    colors: &ThemeColors,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    if legend.is_empty() {
+        return Ok(());
+    }
+    let right = area.dim_in_pixel().0 as i32 - 1;
+    let bottom = LEGEND_ROW * legend.len() as i32 + 10;
+    area.draw(&Rectangle::new(
+        [(LEGEND_GAP, 0), (right, bottom)],
+        colors.background.filled(),
+    ))?;
+    area.draw(&Rectangle::new(
+        [(LEGEND_GAP, 0), (right, bottom)],
+        colors.foreground,
+    ))?;
+    let style = TextStyle::from(LEGEND_FONT.into_font())
+        .color(&colors.foreground)
+        .pos(Pos::new(HPos::Left, VPos::Center));
+    for (i, entry) in legend.iter().enumerate() {
+        let (x, y) = (LEGEND_GAP + 16, 5 + LEGEND_ROW * i as i32 + LEGEND_ROW / 2);
+        match entry.glyph {
+            Glyph::Swatch(color) => area.draw(&Rectangle::new(
+                [(x - 5, y - 5), (x + 5, y + 5)],
+                color.filled(),
+            ))?,
+            Glyph::Line(style) => area.draw(&PathElement::new([(x - 6, y), (x + 6, y)], style))?,
+            Glyph::Triangle(change, color) => {
+                area.draw(&Polygon::new(triangle(change, (x, y)), color.filled()))?
+            }
+        }
+        area.draw_text(&entry.label, &style, (x + 14, y))?;
+    }
     Ok(())
 }
 
-// Convert <short-sha>-<commit-date> to a `DateTime` object, discarding `short-sha`
-fn str_to_datetime(input: &str) -> Result<DateTime<Utc>, Box<dyn Error>> {
-    // Removes the first 8 chars (assuming UTF8) for the `short-sha` and trailing '-'
-    let datetime: &str = input.split_at(8).1;
+// Signed percent change with one decimal, e.g. `+12.5%`
+fn percent_label(percent: f64, locale: &Locale) -> String {
+    let sign = if percent > 0.0 { "+" } else { "" };
+    format!("{}{}%", sign, locale.fixed(percent, 1))
+}
+
+// Mesh tick labels and axis descriptions in the theme's text color, at plotters' default size
+fn mesh_label_style(colors: &ThemeColors) -> (&str, impl SizeDesc, &RGBColor) {
+    ("sans-serif", (12).percent().max(12), &colors.foreground)
+}
+
+// Width of the Y axis label area, leaving room for thousands separators in the labels
+fn value_label_area(locale: &Locale) -> u32 {
+    if locale.thousands_separator.is_empty() {
+        60
+    } else {
+        85
+    }
+}
 
-    DateTime::parse_from_rfc3339(datetime).map_or_else(
-        |e| Err(format!("Failed to parse string into `DateTime`: {}", e).into()),
-        |dt| Ok(dt.with_timezone(&Utc)),
+// Y axis label formatters for values divided by `divisor` (see `Plot::display_scale`), with
+// plotters' own precision, and for whole-number counts
+fn value_labels(
+    locale: &Locale,
+    divisor: f64,
+) -> (impl Fn(&f64) -> String + '_, impl Fn(&f64) -> String + '_) {
+    (
+        move |y: &f64| locale.number(&RangedCoordf64::format(&(y / divisor))),
+        |y: &f64| locale.fixed(*y, 0),
     )
 }
 
+// Draws a single plot onto the given drawing area, independent of the backend
+fn draw_chart<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    name: &str,
+    plot: &Plot,
+    config: &ChartConfig,
+    options: &ChartOptions,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let colors = ThemeColors::of(config.theme);
+    root.fill(&colors.background)?;
+
+    // Points are moved to a day apart per commit, so the date axis spaces them evenly, and each
+    // position is labeled with its commit's SHA instead of the date
+    let indexed;
+    let (plot, commit_labels) = match config.x_axis {
+        XAxis::Date => (plot, None),
+        XAxis::Index => {
+            indexed = by_commit_index(plot, config.history.as_deref());
+            let labels = indexed
+                .lines
+                .values()
+                .flatten()
+                .map(|p| (p.x, p.sha.clone().unwrap_or_default()))
+                .collect::<HashMap<_, _>>();
+            (&indexed, Some(labels))
+        }
+    };
+
+    let area = draw_caption(&root.margin(10, 10, 10, 10), name, &config.caption, &colors)?;
+    // Both axes are padded by a fraction of their range, so the margin looks the same whatever the
+    // values' magnitude
+    // Baselines are kept in view, so a budget far from the values still shows where it is
+    let (y_min, y_max) = options.baselines.iter().fold(
+        (plot.y_axis.min, plot.y_axis.max),
+        |(min, max), (_, value)| (min.min(*value), max.max(*value)),
+    );
+    let y_pad = match plot.kind {
+        // A flat line has no range to pad by, so gets a window around its value instead
+        _ if y_max <= y_min => match y_max.abs() * AXIS_MARGIN {
+            pad if pad > 0.0 => pad,
+            _ => 1.0,
+        },
+        // Counts and byte sizes are whole numbers, so are padded by at least one
+        PlotKind::Count | PlotKind::Memory => ((y_max - y_min) * AXIS_MARGIN).max(1.0),
+        // Padded by at least a thousandth of the values, so noise in the last digits of otherwise
+        // equal times isn't blown up to fill the chart
+        _ => ((y_max - y_min) * AXIS_MARGIN).max(y_max.abs() * 0.001),
+    };
+    let mut y_range = y_min - y_pad..y_max + y_pad;
+    // A single commit's date is widened into a range by a day on either side
+    let (x_min, x_max) = plot.x_axis.bounds().ok_or("Plot has no points")?;
+    let x_pad = match (x_max - x_min).num_milliseconds() {
+        0 => Duration::days(1),
+        span => Duration::milliseconds(((span as f64 * AXIS_MARGIN) as i64).max(1)),
+    };
+    let mut x_range = x_min.checked_sub_signed(x_pad).expect("DateTime underflow")
+        ..x_max.checked_add_signed(x_pad).expect("DateTime overflow");
+
+    // Configured bounds are used as-is, without a buffer. Date bounds don't apply to commit indices
+    let midnight = |date: NaiveDate| DateTime::<Utc>::from_utc(date.and_hms(0, 0, 0), Utc);
+    if let (Some(min), XAxis::Date) = (options.range.x.min, config.x_axis) {
+        x_range.start = midnight(min);
+    }
+    if let (Some(max), XAxis::Date) = (options.range.x.max, config.x_axis) {
+        x_range.end = midnight(max) + Duration::days(1);
+    }
+    if let Some(min) = options.range.y.min {
+        y_range.start = min;
+    }
+    if let Some(max) = options.range.y.max {
+        y_range.end = max;
+    }
+
+    let locale = locale::current();
+    let in_range = |p: &&Point| x_range.contains(&p.x) && y_range.contains(&p.y);
+    // Lines are drawn in sorted params order, which also decides hash collisions between colors,
+    // so groups with the same params get the same colors
+    let lines = plot.lines.iter().collect::<Vec<_>>();
+    let params = lines.iter().map(|line| line.0.as_str()).collect::<Vec<_>>();
+    let line_colors = line_colors(&params, config, &colors);
+    // Translucent lines show where they cluster, since overlapping regions are drawn darker
+    let opacity = config.line_opacity.unwrap_or(1.0).clamp(0.0, 1.0);
+
+    // Points changed from the previous commit, marked with a shape per direction and Okabe-Ito
+    // colors so they stay distinguishable with color vision deficiencies
+    let highlight = &config.highlight;
+    let marks = [
+        (
+            StepChange::Regression,
+            highlight.regressions,
+            REGRESSION_COLOR,
+            "regression",
+        ),
+        (
+            StepChange::Improvement,
+            highlight.improvements,
+            IMPROVEMENT_COLOR,
+            "improvement",
+        ),
+    ]
+    .into_iter()
+    .filter(|mark| mark.1)
+    .map(|(change, _, color, label)| {
+        let points = plot
+            .lines
+            .values()
+            .flat_map(|points| points.windows(2))
+            .filter(|pair| {
+                step_change(&pair[0], &pair[1], &config.regression, plot.kind) == Some(change)
+            })
+            .map(|pair| (&pair[0], &pair[1]))
+            .filter(|(_, latest)| in_range(latest))
+            .collect::<Vec<_>>();
+        (change, color, label, points)
+    })
+    .filter(|mark| !mark.3.is_empty())
+    .collect::<Vec<_>>();
+    let geo_mean = geometric_mean_line(plot, config.geo_mean);
+
+    let mut legend = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let label = match options.noisy.get(line.0.as_str()) {
+            Some(cv) => format!("{} (CV {}%)", line.0, locale.fixed(cv * 100.0, 1)),
+            None => line.0.to_owned(),
+        };
+        legend.push(LegendEntry {
+            label,
+            glyph: Glyph::Swatch(line_colors[i].to_rgba()),
+        });
+        if config.best_ever {
+            legend.push(LegendEntry {
+                label: format!("{} (best)", line.0),
+                glyph: Glyph::Line(line_colors[i].mix(0.6).stroke_width(2)),
+            });
+        }
+    }
+    for (change, color, label, _) in &marks {
+        legend.push(LegendEntry {
+            label: label.to_string(),
+            glyph: Glyph::Triangle(*change, *color),
+        });
+    }
+    if !geo_mean.is_empty() {
+        legend.push(LegendEntry {
+            label: "geo mean".to_owned(),
+            glyph: Glyph::Line(colors.foreground.stroke_width(3)),
+        });
+    }
+
+    // The legend goes in an area right of the plot, so it never hides the latest points
+    let legend_width = if options.legend {
+        legend_width(&area, &legend)?
+    } else {
+        0
+    };
+    let (plot_area, legend_area) =
+        area.split_horizontally(area.dim_in_pixel().0.saturating_sub(legend_width));
+    let mut chart = ChartBuilder::on(&plot_area)
+        .set_label_area_size(LabelAreaPosition::Left, value_label_area(locale))
+        .set_label_area_size(LabelAreaPosition::Bottom, 40)
+        .build_cartesian_2d(x_range.clone(), y_range.clone())?;
+
+    // Ticks between commit positions are left blank
+    // Ticks stay at UTC days in other zones, so they're labeled with the time of day too
+    let utc = locale.timezone == Timezone::Utc;
+    let x_label = |x: &DateTime<Utc>| match &commit_labels {
+        Some(labels) => labels.get(x).cloned().unwrap_or_default(),
+        None if utc => locale.date(x, "%Y-%m-%d"),
+        None => locale.date(x, "%Y-%m-%d %H:%M %:z"),
+    };
+    let (desc, divisor) = plot.display_scale();
+    let (value_label, count_label) = value_labels(locale, divisor);
+    let mut mesh = chart.configure_mesh();
+    mesh.disable_x_mesh()
+        .disable_y_mesh()
+        .axis_style(colors.foreground)
+        .label_style(mesh_label_style(&colors))
+        // Enough ticks for plotters to place one on every commit, as long as the SHAs fit
+        .x_labels(
+            commit_labels
+                .as_ref()
+                .map_or(10, |labels| (labels.len() + 2).min(15)),
+        )
+        .max_light_lines(4)
+        .x_desc(match config.x_axis {
+            XAxis::Date => "Commit Date",
+            XAxis::Index => "Commit",
+        });
+    // Dates keep plotters' own labels unless a format or zone is configured
+    if locale.date_format.is_some() || !utc || commit_labels.is_some() {
+        mesh.x_label_formatter(&x_label);
+    }
+    mesh.y_desc(desc);
+    if plot.kind == PlotKind::Count {
+        mesh.y_label_formatter(&count_label);
+    } else {
+        mesh.y_label_formatter(&value_label);
+    }
+    mesh.draw()?;
+
+    // Bands are drawn before every line, so no line is hidden under another line's band
+    if config.show_band {
+        for (i, line) in lines.iter().enumerate() {
+            let band = band(line.1);
+            if band.len() < 2 {
+                continue;
+            }
+            let outline = band
+                .iter()
+                .map(|&(x, _, upper)| (x, upper))
+                .chain(band.iter().rev().map(|&(x, lower, _)| (x, lower)))
+                .collect::<Vec<_>>();
+            chart.draw_series(std::iter::once(Polygon::new(
+                outline,
+                line_colors[i].mix(0.15 * opacity).filled(),
+            )))?;
+        }
+    }
+
+    // Baselines are dashed across the whole chart, in their line's color or the text color for a
+    // whole group's, and labeled at the right end
+    for (params, value) in &options.baselines {
+        let color = match params {
+            Some(params) => match lines.iter().position(|line| line.0 == params) {
+                Some(i) => line_colors[i],
+                None => continue,
+            },
+            None => colors.foreground,
+        };
+        let area = chart.plotting_area().strip_coord_spec();
+        let (base_x, base_y) = area.get_base_pixel();
+        let ends = [x_range.start, x_range.end].map(|x| {
+            let (x, y) = chart.backend_coord(&(x, *value));
+            (x - base_x, y - base_y)
+        });
+        for dash in dashes(&ends, 8.0, 5.0) {
+            area.draw(&PathElement::new(dash, color.stroke_width(2)))?;
+        }
+        let label = match params {
+            Some(params) => format!("baseline {}", params),
+            None => "baseline".to_owned(),
+        };
+        let style = TextStyle::from(("sans-serif", 11).into_font())
+            .color(&color)
+            .pos(Pos::new(HPos::Right, VPos::Bottom));
+        area.draw_text(&label, &style, (ends[1].0 - 4, ends[1].1 - 3))?;
+    }
+    // Points on the wrong side of their baseline: above it, or below it for throughput
+    let beyond_baseline = |params: &str, p: &Point| match options.baseline_for(params) {
+        Some(baseline) if plot.kind.higher_is_better() => p.y < baseline,
+        Some(baseline) => p.y > baseline,
+        None => false,
+    };
+
+    // Draws the lines of benchmark data points, one line/color per set of bench ID params e.g. `rc=100`
+    for (i, line) in lines.iter().enumerate() {
+        let color = line_colors[i];
+        let raw_color = color.mix(opacity);
+
+        // Draw lines between each point
+        chart.draw_series(LineSeries::new(
+            line.1.iter().map(|p| (p.x, p.y)),
+            raw_color,
+        ))?;
+
+        // Draw each point's confidence interval as a vertical bar behind its dot, so changes
+        // within the noise are recognizable. Points without an interval only get the dot
+        if config.error_bars {
+            chart.draw_series(line.1.iter().filter(in_range).filter_map(|p| {
+                let interval = p.interval?;
+                Some(ErrorBar::new_vertical(
+                    p.x,
+                    interval.lower,
+                    p.y,
+                    interval.upper,
+                    raw_color,
+                    6,
+                ))
+            }))?;
+        }
+
+        // Draw dots on each point, skipping those outside fixed axis bounds. Plotters clamps
+        // out-of-range lines to the chart edges, but dots piled up there would look like data
+        chart.draw_series(line.1.iter().filter(in_range).map(|p| {
+            let color = if beyond_baseline(line.0, p) {
+                REGRESSION_COLOR.mix(opacity)
+            } else {
+                raw_color
+            };
+            Circle::new((p.x, p.y), 3, color.filled())
+        }))?;
+
+        // The smoothed line is dashed in a lighter shade, so the raw line stays readable under it
+        if let Some(smoothed) = smooth(line.1, config.smoothing) {
+            let area = chart.plotting_area().strip_coord_spec();
+            let (base_x, base_y) = area.get_base_pixel();
+            let pixels = smoothed
+                .iter()
+                .map(|p| {
+                    let (x, y) = chart.backend_coord(p);
+                    (x - base_x, y - base_y)
+                })
+                .collect::<Vec<_>>();
+            let style = colors.faded(color).mix(opacity).stroke_width(2);
+            for dash in dashes(&pixels, 8.0, 5.0) {
+                area.draw(&PathElement::new(dash, style))?;
+            }
+        }
+
+        // Draw the best value achieved so far as a step line, so regressions from it stand out
+        if config.best_ever {
+            chart.draw_series(LineSeries::new(
+                cumulative_min_steps(line.1),
+                color.mix(0.6 * opacity).stroke_width(2),
+            ))?;
+        }
+    }
+
+    // Labels each point with its commit's short SHA, in its line's color. A label that would
+    // overlap one already placed is skipped, so commits close together in time stay readable
+    if config.sha_labels {
+        let (label_w, label_h) = (44, 12);
+        let mut placed: Vec<(i32, i32)> = vec![];
+        for (i, line) in lines.iter().enumerate() {
+            let font = ("sans-serif", 10).into_font().color(&line_colors[i]);
+            let labels = line
+                .1
+                .iter()
+                .filter(in_range)
+                .filter_map(|p| {
+                    let sha = p.sha.clone()?;
+                    let (x, y) = chart.backend_coord(&(p.x, p.y));
+                    let overlaps = placed
+                        .iter()
+                        .any(|&(px, py)| (x - px).abs() < label_w && (y - py).abs() < label_h);
+                    if overlaps {
+                        return None;
+                    }
+                    placed.push((x, y));
+                    Some(EmptyElement::at((p.x, p.y)) + Text::new(sha, (4, -14), font.clone()))
+                })
+                .collect::<Vec<_>>();
+            chart.draw_series(labels)?;
+        }
+    }
+
+    // Each step's percent change goes beside the middle of its segment, pushed off the line along
+    // the segment's upward normal by enough that the label's half width clears a steep segment
+    if config.deltas.show {
+        let area = chart.plotting_area().strip_coord_spec();
+        let (base_x, base_y) = area.get_base_pixel();
+        let muted = colors.faded(colors.foreground);
+        let style = TextStyle::from(("sans-serif", 10).into_font())
+            .color(&muted)
+            .pos(Pos::new(HPos::Center, VPos::Center));
+        for (_, points) in &lines {
+            for pair in points.windows(2) {
+                let (previous, latest) = (&pair[0], &pair[1]);
+                let change = latest.y / previous.y - 1.0;
+                let labeled = in_range(&previous)
+                    && in_range(&latest)
+                    && change.is_finite()
+                    && change.abs() >= config.deltas.min_change;
+                if !labeled {
+                    continue;
+                }
+                let label = percent_label(change * 100.0, locale);
+                let (x0, y0) = chart.backend_coord(&(previous.x, previous.y));
+                let (x1, y1) = chart.backend_coord(&(latest.x, latest.y));
+                let (dx, dy) = ((x1 - x0) as f64, (y1 - y0) as f64);
+                let length = dx.hypot(dy).max(1.0);
+                // Pixel Y grows downwards, so the normal pointing up has a negative Y
+                let (nx, ny) = if dx >= 0.0 {
+                    (dy / length, -dx / length)
+                } else {
+                    (-dy / length, dx / length)
+                };
+                let half_width = label.chars().count() as f64 * 3.0;
+                let push = 8.0 + half_width * nx.abs();
+                let x = (x0 + x1) as f64 / 2.0 + nx * push;
+                let y = (y0 + y1) as f64 / 2.0 + ny * push;
+                area.draw_text(&label, &style, (x as i32 - base_x, y as i32 - base_y))?;
+            }
+        }
+    }
+
+    for (change, color, _, points) in marks {
+        chart.draw_series(points.iter().map(|(_, p)| {
+            EmptyElement::at((p.x, p.y)) + Polygon::new(triangle(change, (0, 0)), color.filled())
+        }))?;
+        // The percent change goes beyond the triangle's tip, away from the line
+        if highlight.labels {
+            let (offset, v_pos) = match change {
+                StepChange::Regression => (-10, VPos::Bottom),
+                StepChange::Improvement => (10, VPos::Top),
+            };
+            let font = TextStyle::from(("sans-serif", 11).into_font())
+                .color(&color)
+                .pos(Pos::new(HPos::Center, v_pos));
+            chart.draw_series(points.iter().map(|(previous, p)| {
+                let label = percent_label((p.y / previous.y - 1.0) * 100.0, locale);
+                EmptyElement::at((p.x, p.y)) + Text::new(label, (0, offset), font.clone())
+            }))?;
+        }
+        // The commit's subject goes beside the triangle, explaining the change without leaving
+        // the chart
+        let font = TextStyle::from(("sans-serif", 11).into_font())
+            .color(&colors.foreground)
+            .pos(Pos::new(HPos::Left, VPos::Center));
+        chart.draw_series(points.iter().filter_map(|(_, p)| {
+            let subject = config.commit_messages.get(p.sha.as_deref()?)?;
+            let subject = match subject.char_indices().nth(SUBJECT_CHARS) {
+                Some((end, _)) => format!("{}…", &subject[..end]),
+                None => subject.clone(),
+            };
+            Some(EmptyElement::at((p.x, p.y)) + Text::new(subject, (10, 0), font.clone()))
+        }))?;
+    }
+
+    if let Some(reached) = options
+        .target
+        .and_then(|target| target_reached(plot, target))
+    {
+        let label = format!("target reached {}", x_label(&reached.x));
+        chart.draw_series(std::iter::once(
+            EmptyElement::at((reached.x, reached.y))
+                + Circle::new((0, 0), 8, colors.foreground.stroke_width(2))
+                + Text::new(
+                    label,
+                    (10, -20),
+                    ("sans-serif", 14).into_font().color(&colors.foreground),
+                ),
+        ))?;
+    }
+
+    // The geometric mean is drawn last so it stays visible on top of the per-params lines
+    if !geo_mean.is_empty() {
+        chart.draw_series(LineSeries::new(geo_mean, colors.foreground.stroke_width(3)))?;
+    }
+
+    if options.legend {
+        draw_legend(&legend_area, &legend, &colors)?;
+    }
+
+    Ok(())
+}
+
+// A copy of `plot` with each commit's points moved to `index_date(i)` for the commit's index `i`.
+// Commits are in the order of `history`, the repository's full SHAs oldest first, if it has every
+// commit's SHA, and in date order otherwise
+fn by_commit_index(plot: &Plot, history: Option<&[String]>) -> Plot {
+    let mut commits = plot
+        .lines
+        .values()
+        .flatten()
+        .map(|p| (p.x, p.sha.as_deref()))
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let positions = history.and_then(|history| {
+        commits
+            .iter()
+            .map(|(_, sha)| {
+                let sha = (*sha)?;
+                history.iter().position(|full| full.starts_with(sha))
+            })
+            .collect::<Option<Vec<_>>>()
+    });
+    if let Some(positions) = positions {
+        let mut ordered = commits.into_iter().zip(positions).collect::<Vec<_>>();
+        ordered.sort_by_key(|(_, position)| *position);
+        commits = ordered.into_iter().map(|(commit, _)| commit).collect();
+    }
+    let index = commits
+        .iter()
+        .enumerate()
+        .map(|(i, (x, _))| (*x, index_date(i)))
+        .collect::<HashMap<_, _>>();
+
+    let lines = plot
+        .lines
+        .iter()
+        .map(|(params, points)| {
+            let points = points
+                .iter()
+                .map(|p| Point {
+                    x: index[&p.x],
+                    ..p.clone()
+                })
+                .collect();
+            (params.clone(), points)
+        })
+        .collect();
+    let mut plot = Plot {
+        lines,
+        unit: plot.unit.clone(),
+        metric: plot.metric,
+        ..Plot::new(plot.kind)
+    };
+    plot.sort_points();
+    plot
+}
+
+// Position of the `i`th commit on an `XAxis::Index` chart, at midnight `i` days after the epoch,
+// so the date axis places ticks on commits
+fn index_date(i: usize) -> DateTime<Utc> {
+    DateTime::<Utc>::from_utc(NaiveDate::from_ymd(1970, 1, 1).and_hms(0, 0, 0), Utc)
+        + Duration::days(i as i64)
+}
+
+// The earliest point of any line that reaches `target`, i.e. falls below it, or for higher-is-better
+// plots like throughput rises to it
+fn target_reached(plot: &Plot, target: f64) -> Option<&Point> {
+    let reached = |p: &&Point| {
+        if plot.kind.higher_is_better() {
+            p.y >= target
+        } else {
+            p.y < target
+        }
+    };
+    plot.lines
+        .values()
+        .filter_map(|points| points.iter().find(reached))
+        .min_by_key(|p| p.x)
+}
+
+// Geometric mean across params at each commit, which unlike the arithmetic mean isn't dominated by
+// the params with the largest values. Non-positive values have no logarithm and are treated as
+// missing. Empty when disabled
+fn geometric_mean_line(plot: &Plot, mode: GeoMean) -> Vec<(DateTime<Utc>, f64)> {
+    if mode == GeoMean::Off {
+        return vec![];
+    }
+    let mut logs_by_commit = BTreeMap::<DateTime<Utc>, Vec<f64>>::new();
+    for point in plot.lines.values().flatten().filter(|p| p.y > 0.0) {
+        logs_by_commit
+            .entry(point.x)
+            .or_default()
+            .push(point.y.ln());
+    }
+    logs_by_commit
+        .into_iter()
+        .filter(|(_, logs)| mode == GeoMean::Available || logs.len() == plot.lines.len())
+        .map(|(x, logs)| (x, (logs.iter().sum::<f64>() / logs.len() as f64).exp()))
+        .collect()
+}
+
+// Draws a plot as a grid of params (Y) by commits (X), colored by value, with a color scale on the
+// right. Params without a value at a commit are left blank.
+fn draw_heatmap<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    name: &str,
+    plot: &Plot,
+    config: &ChartConfig,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let colors = ThemeColors::of(config.theme);
+    root.fill(&colors.background)?;
+    let locale = locale::current();
+
+    let area = draw_caption(&root.margin(10, 10, 10, 10), name, &config.caption, &colors)?;
+    let (grid_area, scale_area) =
+        area.split_horizontally(area.dim_in_pixel().0.saturating_sub(100));
+
+    let mut commits = plot
+        .lines
+        .values()
+        .flatten()
+        .map(|p| p.x)
+        .collect::<Vec<_>>();
+    commits.sort();
+    commits.dedup();
+    let params = plot.lines.keys().collect::<Vec<_>>();
+    if commits.is_empty() {
+        return Ok(());
+    }
+
+    let (min, max) = (plot.y_axis.min, plot.y_axis.max);
+    let cell_color = |y: f64| {
+        if max > min {
+            ViridisRGB::get_color_normalized(y, min, max)
+        } else {
+            ViridisRGB::get_color(0.5)
+        }
+    };
+
+    let mut chart = ChartBuilder::on(&grid_area)
+        .set_label_area_size(LabelAreaPosition::Left, 80)
+        .set_label_area_size(LabelAreaPosition::Bottom, 40)
+        .build_cartesian_2d(
+            (0..commits.len() - 1).into_segmented(),
+            (0..params.len() - 1).into_segmented(),
+        )?;
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .disable_y_mesh()
+        .axis_style(colors.foreground)
+        .label_style(mesh_label_style(&colors))
+        .x_labels(commits.len().min(10))
+        .y_labels(params.len())
+        .x_label_formatter(&|i| match i {
+            SegmentValue::CenterOf(i) => locale.date(&commits[*i], "%Y-%m-%d"),
+            _ => String::new(),
+        })
+        .y_label_formatter(&|j| match j {
+            SegmentValue::CenterOf(j) => params[*j].to_string(),
+            _ => String::new(),
+        })
+        .x_desc("Commit Date")
+        .y_desc("Params")
+        .draw()?;
+
+    for (j, params) in params.iter().enumerate() {
+        chart.draw_series(plot.lines[*params].iter().map(|p| {
+            let i = commits.binary_search(&p.x).unwrap();
+            Rectangle::new(
+                [
+                    (SegmentValue::Exact(i), SegmentValue::Exact(j)),
+                    (SegmentValue::Exact(i + 1), SegmentValue::Exact(j + 1)),
+                ],
+                cell_color(p.y).filled(),
+            )
+        }))?;
+    }
+
+    // Color scale: a vertical gradient from the max value at the top to the min at the bottom
+    let scale_area = scale_area.margin(10, 50, 20, 10);
+    let (scale_w, scale_h) = scale_area.dim_in_pixel();
+    let steps = 100;
+    for step in 0..steps {
+        let (y0, y1) = (
+            (scale_h * step / steps) as i32,
+            (scale_h * (step + 1) / steps) as i32,
+        );
+        let value = max - (max - min) * step as f64 / (steps - 1) as f64;
+        scale_area.draw(&Rectangle::new(
+            [(0, y0), (20, y1)],
+            cell_color(value).filled(),
+        ))?;
+    }
+    let (desc, divisor) = plot.display_scale();
+    // Unscaled values are rounded to whole numbers, scaled ones keep their fraction
+    let scale_label = |value: f64| {
+        if divisor == 1.0 {
+            locale.fixed(value, 0)
+        } else {
+            locale.number(&RangedCoordf64::format(&(value / divisor)))
+        }
+    };
+    let label_style = TextStyle::from(("sans-serif", 12).into_font()).color(&colors.foreground);
+    scale_area.draw_text(&scale_label(max), &label_style, (24, 0))?;
+    scale_area.draw_text(
+        &scale_label(min),
+        &label_style.pos(Pos::new(HPos::Left, VPos::Bottom)),
+        (24, scale_h as i32),
+    )?;
+    let desc_style = label_style.transform(FontTransform::Rotate90);
+    scale_area.draw_text(
+        &desc,
+        &desc_style,
+        (scale_w as i32 - 12, scale_h as i32 / 2),
+    )?;
+
+    Ok(())
+}
+
+// A group's results at a single commit, with each params placed by its numeric value
+struct SweepProfile {
+    commit: DateTime<Utc>,
+    // Name of the swept param (e.g. `rc`), if every params has the same one
+    param_name: Option<String>,
+    // (numeric param, value) sorted by param
+    points: Vec<(f64, f64)>,
+}
+
+// Collects the values at the selected commit for every params with a numeric part. Returns `None`
+// if the group has no results at that commit or no numeric params
+fn sweep_profile(plot: &Plot, at: SweepAt) -> Option<SweepProfile> {
+    let commits = plot.lines.values().flatten().map(|p| p.x);
+    let commit = match at {
+        SweepAt::Latest => commits.max()?,
+        SweepAt::Date(date) => commits.filter(|x| x.date_naive() == date).max()?,
+    };
+
+    let mut names = vec![];
+    let mut points = vec![];
+    for (params, line) in plot.lines.iter() {
+        let Some((name, value)) = numeric_param(params) else {
+            continue;
+        };
+        if let Some(p) = line.iter().find(|p| p.x == commit) {
+            names.push(name);
+            points.push((value, p.y));
+        }
+    }
+    if points.is_empty() {
+        return None;
+    }
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    names.dedup();
+    let param_name = match names.as_slice() {
+        [name] if !name.is_empty() => Some(name.to_string()),
+        _ => None,
+    };
+    Some(SweepProfile {
+        commit,
+        param_name,
+        points,
+    })
+}
+
+// Splits params like `rc=100` into the text before the first number (`rc`) and the number (100)
+fn numeric_param(params: &str) -> Option<(&str, f64)> {
+    let start = params.find(|c: char| c.is_ascii_digit())?;
+    let len = params[start..]
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(params.len() - start);
+    let value = params[start..start + len].parse().ok()?;
+    let name = params[..start].trim_end_matches(['=', ':', '-', '_', ' ']);
+    Some((name, value))
+}
+
+// Draws a sweep profile as a single line of value against the numeric param
+fn draw_sweep<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    name: &str,
+    plot: &Plot,
+    profile: &SweepProfile,
+    config: &ChartConfig,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let colors = ThemeColors::of(config.theme);
+    root.fill(&colors.background)?;
+
+    let locale = locale::current();
+    let caption = format!("{} @ {}", name, locale.date(&profile.commit, "%Y-%m-%d"));
+    let area = draw_caption(
+        &root.margin(10, 10, 10, 10),
+        &caption,
+        &config.caption,
+        &colors,
+    )?;
+
+    // Pad both axes by a fraction of their range, or by one unit if there is only a single value
+    let padded = |values: &mut dyn Iterator<Item = f64>| {
+        let (min, max) = values.fold((f64::MAX, f64::MIN), |(min, max), v| {
+            (min.min(v), max.max(v))
+        });
+        let pad = if max > min { (max - min) * 0.05 } else { 1.0 };
+        min - pad..max + pad
+    };
+    let x_range = padded(&mut profile.points.iter().map(|p| p.0));
+    let y_range = padded(&mut profile.points.iter().map(|p| p.1));
+
+    let mut chart = ChartBuilder::on(&area)
+        .set_label_area_size(LabelAreaPosition::Left, value_label_area(locale))
+        .set_label_area_size(LabelAreaPosition::Bottom, 40)
+        .build_cartesian_2d(x_range, y_range)?;
+
+    let param_label = |x: &f64| locale.number(&x.to_string());
+    let (desc, divisor) = plot.display_scale();
+    let (value_label, count_label) = value_labels(locale, divisor);
+    let mut mesh = chart.configure_mesh();
+    mesh.disable_x_mesh()
+        .disable_y_mesh()
+        .axis_style(colors.foreground)
+        .label_style(mesh_label_style(&colors))
+        .max_light_lines(4)
+        .x_desc(profile.param_name.as_deref().unwrap_or("Param"))
+        .x_label_formatter(&param_label)
+        .y_desc(desc);
+    if plot.kind == PlotKind::Count {
+        mesh.y_label_formatter(&count_label);
+    } else {
+        mesh.y_label_formatter(&value_label);
+    }
+    mesh.draw()?;
+
+    let color = colors.series(0).to_rgba();
+    chart.draw_series(LineSeries::new(profile.points.iter().copied(), color))?;
+    chart.draw_series(
+        profile
+            .points
+            .iter()
+            .map(|p| Circle::new(*p, 3, color.filled())),
+    )?;
+
+    Ok(())
+}
+
+// Renders a bar chart per group of each line's values at the two `--compare` commits into `sink`,
+// named like the group's chart with a `.compare` suffix
+pub fn generate_comparisons(
+    data: &Plots,
+    comparisons: &[Comparison],
+    shas: (&str, &str),
+    config: &ChartConfig,
+    sink: &mut dyn ChartSink,
+) -> Result<(), Box<dyn Error>> {
+    if config.format == OutputFormat::Vega {
+        return Err("Comparisons can't be written as Vega-Lite specs".into());
+    }
+    let (width, height) = config.size();
+    for (group, plot) in data.0.iter() {
+        let bars = comparisons
+            .iter()
+            .filter(|comparison| comparison.group == group)
+            .collect::<Vec<_>>();
+        if bars.is_empty() {
+            continue;
+        }
+        let name = &*plot_name(config, group);
+        let chart = render_chart!(config, width, height, |root| {
+            draw_comparison(root, name, plot, &bars, shas, config)
+        })?;
+        let file_name = chart_file_name(config, group, &format!("{}.compare", name), plot, None);
+        sink.write_chart(&file_name, &chart)?;
+    }
+    Ok(())
+}
+
+// Draws a pair of bars per params, the base commit's then the head's, labeled with the percent
+// change between them
+fn draw_comparison<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    name: &str,
+    plot: &Plot,
+    bars: &[&Comparison],
+    (base, head): (&str, &str),
+    config: &ChartConfig,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let colors = ThemeColors::of(config.theme);
+    root.fill(&colors.background)?;
+    let locale = locale::current();
+    let caption = format!("{}: {} vs {}", name, base, head);
+    let area = draw_caption(
+        &root.margin(10, 10, 10, 10),
+        &caption,
+        &config.caption,
+        &colors,
+    )?;
+
+    let bar_colors = [colors.series(0), colors.series(1)];
+    let legend = [base, head]
+        .into_iter()
+        .zip(bar_colors)
+        .map(|(sha, color)| LegendEntry {
+            label: sha.to_owned(),
+            glyph: Glyph::Swatch(color.to_rgba()),
+        })
+        .collect::<Vec<_>>();
+    let (plot_area, legend_area) = area.split_horizontally(
+        area.dim_in_pixel()
+            .0
+            .saturating_sub(legend_width(&area, &legend)?),
+    );
+
+    // Bars start from zero, with headroom above the tallest for its label. Each params is centered
+    // on a whole number, where its X axis label is
+    let tallest = bars
+        .iter()
+        .map(|bar| bar.base.y.max(bar.head.y))
+        .fold(0.0, f64::max);
+    let y_max = if tallest > 0.0 { tallest * 1.15 } else { 1.0 };
+    let mut chart = ChartBuilder::on(&plot_area)
+        .set_label_area_size(LabelAreaPosition::Left, value_label_area(locale))
+        .set_label_area_size(LabelAreaPosition::Bottom, 40)
+        .build_cartesian_2d(-0.5..bars.len() as f64 - 0.5, 0.0..y_max)?;
+
+    let params_label = |x: &f64| match bars.get(x.round() as usize) {
+        Some(bar) if (x - x.round()).abs() < 1e-9 && *x > -0.5 => bar.params.to_owned(),
+        _ => String::new(),
+    };
+    let (desc, divisor) = plot.display_scale();
+    let (value_label, count_label) = value_labels(locale, divisor);
+    let mut mesh = chart.configure_mesh();
+    mesh.disable_x_mesh()
+        .disable_y_mesh()
+        .axis_style(colors.foreground)
+        .label_style(mesh_label_style(&colors))
+        .max_light_lines(4)
+        .x_labels(bars.len())
+        .x_label_formatter(&params_label)
+        .x_desc("Params")
+        .y_desc(desc);
+    if plot.kind == PlotKind::Count {
+        mesh.y_label_formatter(&count_label);
+    } else {
+        mesh.y_label_formatter(&value_label);
+    }
+    mesh.draw()?;
+
+    let label_style = TextStyle::from(LEGEND_FONT.into_font())
+        .color(&colors.foreground)
+        .pos(Pos::new(HPos::Center, VPos::Bottom));
+    for (i, bar) in bars.iter().enumerate() {
+        let x = i as f64;
+        chart.draw_series(
+            [(-0.4, bar.base), (0.02, bar.head)]
+                .into_iter()
+                .zip(bar_colors)
+                .map(|((offset, point), color)| {
+                    Rectangle::new(
+                        [(x + offset, 0.0), (x + offset + 0.38, point.y)],
+                        color.filled(),
+                    )
+                }),
+        )?;
+        let change = locale.number(&format!("{:+.1}%", bar.percent_change()));
+        chart.draw_series(std::iter::once(
+            EmptyElement::at((x, bar.base.y.max(bar.head.y)))
+                + Text::new(change, (0, -4), label_style.clone()),
+        ))?;
+    }
+
+    draw_legend(&legend_area, &legend, &colors)
+}
+
+// Draws the caption at the top of `area`, fitting it to the area's width, and returns the
+// remaining area below it for the chart itself
+fn draw_caption<DB: DrawingBackend>(
+    area: &DrawingArea<DB, Shift>,
+    caption: &str,
+    config: &CaptionConfig,
+    colors: &ThemeColors,
+) -> Result<DrawingArea<DB, Shift>, Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let style = TextStyle::from(("sans-serif", 40).into_font()).color(&colors.foreground);
+    let width = area.dim_in_pixel().0;
+    let (_, text_h) = area.estimate_text_size(caption, &style)?;
+    let fits = |s: &str| {
+        area.estimate_text_size(s, &style)
+            .map_or(true, |(w, _)| w <= width)
+    };
+
+    let lines = match config.overflow {
+        CaptionOverflow::Wrap => wrap_caption(caption, fits),
+        CaptionOverflow::Truncate => vec![truncate_caption(caption, fits)],
+    };
+
+    // Matches the padding plotters uses for `ChartBuilder::caption`
+    let y_padding = (text_h / 2).min(5);
+    let (x, style) = match config.align {
+        CaptionAlign::Left => (0, style.pos(Pos::new(HPos::Left, VPos::Top))),
+        CaptionAlign::Center => (
+            width as i32 / 2,
+            style.pos(Pos::new(HPos::Center, VPos::Top)),
+        ),
+    };
+    for (i, line) in lines.iter().enumerate() {
+        let y = (y_padding + i as u32 * text_h) as i32;
+        area.draw_text(line, &style, (x, y))?;
+    }
+
+    let caption_h = y_padding * 2 + lines.len() as u32 * text_h;
+    Ok(area.split_vertically(caption_h).1)
+}
+
+// Greedily breaks the caption into lines that fit, preferring to break after a separator
+fn wrap_caption(caption: &str, fits: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut lines = vec![];
+    let mut rest = caption;
+    while !fits(rest) {
+        // Always take at least one char per line so a very narrow area still terminates
+        let mut end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        for (i, c) in rest.char_indices().skip(1) {
+            if !fits(&rest[..i + c.len_utf8()]) {
+                break;
+            }
+            end = i + c.len_utf8();
+        }
+        if let Some(pos) = rest[..end].rfind([' ', '-', '/', ':', '_', '=']) {
+            if pos > 0 {
+                end = pos + 1;
+            }
+        }
+        lines.push(rest[..end].trim_end().to_owned());
+        rest = rest[end..].trim_start();
+    }
+    if !rest.is_empty() {
+        lines.push(rest.to_owned());
+    }
+    lines
+}
+
+// Shortens the caption to the longest prefix that fits with a trailing ellipsis
+fn truncate_caption(caption: &str, fits: impl Fn(&str) -> bool) -> String {
+    if fits(caption) {
+        return caption.to_owned();
+    }
+    caption
+        .char_indices()
+        .rev()
+        .map(|(i, _)| format!("{}…", &caption[..i]))
+        .find(|s| fits(s))
+        .unwrap_or_else(|| "…".to_owned())
+}
+
+// Applies the configured smoothing algorithm to a line's sorted points
+// Returns `None` when smoothing is disabled, so only the raw line is drawn
+fn smooth(points: &[Point], smoothing: Smoothing) -> Option<Vec<(DateTime<Utc>, f64)>> {
+    let smoothed = match smoothing {
+        Smoothing::None => return None,
+        Smoothing::MovingAverage(window) => trailing_windows(points, window)
+            .map(|w| w.iter().map(|p| p.y).sum::<f64>() / w.len() as f64)
+            .collect::<Vec<_>>(),
+        Smoothing::Median(window) => trailing_windows(points, window)
+            .map(|w| {
+                let mut ys = w.iter().map(|p| p.y).collect::<Vec<_>>();
+                ys.sort_by(|a, b| a.total_cmp(b));
+                let mid = ys.len() / 2;
+                if ys.len() % 2 == 0 {
+                    (ys[mid - 1] + ys[mid]) / 2.0
+                } else {
+                    ys[mid]
+                }
+            })
+            .collect(),
+        Smoothing::Ema(alpha) => points
+            .iter()
+            .scan(None, |ema: &mut Option<f64>, p| {
+                let next = ema.map_or(p.y, |prev| alpha * p.y + (1.0 - alpha) * prev);
+                *ema = Some(next);
+                Some(next)
+            })
+            .collect(),
+    };
+    Some(points.iter().map(|p| p.x).zip(smoothed).collect())
+}
+
+// Splits a polyline into dashes of `dash` pixels separated by `gap` pixels, measured along the line
+// so the pattern continues across vertices
+fn dashes(points: &[(i32, i32)], dash: f64, gap: f64) -> Vec<Vec<(i32, i32)>> {
+    let mut dashes = vec![];
+    let mut current = vec![];
+    // Distance left in the current dash or gap
+    let (mut drawing, mut left) = (true, dash);
+    for pair in points.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        let (dx, dy) = ((x1 - x0) as f64, (y1 - y0) as f64);
+        let len = dx.hypot(dy);
+        let at = |t: f64| {
+            (
+                x0 + (dx * t / len).round() as i32,
+                y0 + (dy * t / len).round() as i32,
+            )
+        };
+        if drawing && current.is_empty() {
+            current.push(pair[0]);
+        }
+        let mut t = 0.0;
+        while len - t > left {
+            t += left;
+            if drawing {
+                current.push(at(t));
+                dashes.push(std::mem::take(&mut current));
+                (drawing, left) = (false, gap);
+            } else {
+                current.push(at(t));
+                (drawing, left) = (true, dash);
+            }
+        }
+        left -= len - t;
+        if drawing {
+            current.push(pair[1]);
+        }
+    }
+    if current.len() > 1 {
+        dashes.push(current);
+    }
+    dashes
+}
+
+// Running minimum of a line's sorted points, as the vertices of a step line that only moves
+// down when a new best value is reached
+fn cumulative_min_steps(points: &[Point]) -> Vec<(DateTime<Utc>, f64)> {
+    let mut steps = vec![];
+    let mut best = f64::INFINITY;
+    for p in points {
+        if p.y < best {
+            if best.is_finite() {
+                steps.push((p.x, best));
+            }
+            best = p.y;
+        }
+        steps.push((p.x, best));
+    }
+    steps
+}
+
+// Number of trailing points whose min and max bound a band for lines without confidence intervals
+const BAND_WINDOW: usize = 5;
+
+// (x, lower, upper) edges of a line's band at each point: the confidence bounds if every point has
+// them, and otherwise the min and max of the trailing `BAND_WINDOW` points
+fn band(points: &[Point]) -> Vec<(DateTime<Utc>, f64, f64)> {
+    if let Some(intervals) = points
+        .iter()
+        .map(|p| p.interval)
+        .collect::<Option<Vec<_>>>()
+    {
+        return points
+            .iter()
+            .zip(intervals)
+            .map(|(p, i)| (p.x, i.lower, i.upper))
+            .collect();
+    }
+    points
+        .iter()
+        .zip(trailing_windows(points, BAND_WINDOW))
+        .map(|(p, window)| {
+            let (min, max) = window.iter().fold((f64::MAX, f64::MIN), |(min, max), p| {
+                (min.min(p.y), max.max(p.y))
+            });
+            (p.x, min, max)
+        })
+        .collect()
+}
+
+// Yields the window of up to `size` points ending at each point, shorter at the start of the line
+fn trailing_windows(points: &[Point], size: usize) -> impl Iterator<Item = &[Point]> {
+    (0..points.len()).map(move |i| &points[(i + 1).saturating_sub(size)..=i])
+}
+
+// The commit a result was benchmarked at, parsed from a bench name <sha>-<commit-date>
+struct BenchCommit<'a> {
+    sha: Option<&'a str>,
+    date: DateTime<Utc>,
+}
+
+// The SHA can be of any length, so the date starts after the first `-` followed by a valid RFC 3339
+// timestamp, or is the whole name for bench names without a SHA
+fn parse_bench_name(name: &str) -> Result<BenchCommit, Box<dyn Error>> {
+    let splits = name.match_indices('-').map(|(i, _)| {
+        (
+            Some(&name[..i]).filter(|sha| !sha.is_empty()),
+            &name[i + 1..],
+        )
+    });
+    std::iter::once((None, name))
+        .chain(splits)
+        .find_map(|(sha, date)| {
+            let date = DateTime::parse_from_rfc3339(date).ok()?;
+            Some(BenchCommit {
+                sha,
+                date: date.with_timezone(&Utc),
+            })
+        })
+        .ok_or_else(|| {
+            format!(
+                "Bench name `{}` doesn't contain an RFC 3339 commit date, e.g. `abc1234-2024-01-30T19:07:04-05:00`",
+                name
+            )
+            .into()
+        })
+}
+
+// The first 7 characters of a SHA, as stored on points. Bench names aren't checked for hex, so this
+// cuts at a character boundary rather than a byte offset
+pub(crate) fn short_sha(sha: &str) -> &str {
+    sha.char_indices()
+        .nth(7)
+        .map_or(sha, |(end, _)| &sha[..end])
+}
+
 // Plots of benchmark results over time/Git history. This data structure is persistent between runs,
 // saved to disk in `plot-data.json`, and is meant to be append-only to preserve historical results.
 //
@@ -103,94 +1866,629 @@ fn str_to_datetime(input: &str) -> Result<DateTime<Utc>, Box<dyn Error>> {
 // information to view multiple benchmark input results on the same graph (e.g. fib-10 and fib-20),
 // since they are expected to be different. Instead, we group different benchmark parameters
 // (e.g. `rc` value) onto the same graph to compare/contrast their impact on performance.
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Plots(HashMap<String, Plot>);
+//
+// Groups and lines are kept sorted by name, so charts are rendered and logged, and `plot-data.json`
+// is written, in the same order on every run
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Plots(pub(crate) BTreeMap<String, Plot>);
+
+// Version of the `plot-data.json` layout, bumped along with a step in `Plots::from_json` whenever
+// stored data can't be read by defaulting the new fields
+pub const PLOT_DATA_VERSION: u64 = 1;
+
+// Layout of `plot-data.json`, with the version it was written in beside the plots
+#[derive(Serialize)]
+struct PlotData<'a> {
+    version: u64,
+    plots: &'a Plots,
+}
 
 impl Plots {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self(BTreeMap::new())
+    }
+
+    // Reads plot data written by this or any earlier version, upgrading it to the current layout.
+    // Files from before the layout was versioned are a bare map of the plots, i.e. version 0.
+    // Fields missing from older data are defaulted, and unknown ones ignored, then the axes are
+    // recomputed from the points
+    pub fn from_json(s: &str) -> Result<Self, Box<dyn Error>> {
+        let mut value: serde_json::Value = serde_json::from_str(s)?;
+        // A group could be named `version`, but its value would be a plot rather than a number
+        let (mut version, mut plots) = match value.get("version").and_then(|v| v.as_u64()) {
+            Some(version) => (version, value["plots"].take()),
+            None => (0, value),
+        };
+        if version > PLOT_DATA_VERSION {
+            return Err(format!(
+                "plot data is version {}, newer than the supported version {}",
+                version, PLOT_DATA_VERSION
+            )
+            .into());
+        }
+        while version < PLOT_DATA_VERSION {
+            plots = match version {
+                // Version 1 only added the envelope
+                0 => plots,
+                _ => unreachable!("no upgrade from plot data version {}", version),
+            };
+            version += 1;
+        }
+        let mut plots: Plots = serde_json::from_value(plots)?;
+        // Lines are stored sorted, which the sort confirms in one pass, but a hand-edited file may
+        // not be, and new points are inserted assuming they are
+        for plot in plots.0.values_mut() {
+            plot.sort_points();
+        }
+        Ok(plots)
+    }
+
+    // Serializes the plots in the current `plot-data.json` layout
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&PlotData {
+            version: PLOT_DATA_VERSION,
+            plots: self,
+        })
     }
 
     // Converts a list of deserialized Criterion benchmark results into a plotting-friendly format,
     // and adds the data to the `Plots` struct.
-    pub fn add_data(&mut self, bench_data: &Vec<BenchData>) {
+    // Returns the number of points that were added or changed
+    pub fn add_data(
+        &mut self,
+        bench_data: &Vec<BenchData>,
+        config: &IngestConfig,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut changed = 0;
+        // Groups whose Y range may have shrunk, since a point was replaced by a lower or higher one
+        let mut replaced = BTreeSet::new();
         for bench in bench_data {
-            let commit_date = str_to_datetime(&bench.id.bench_name).expect("Timestamp parse error");
+            let group_name = config.group_aliases.resolve(&bench.id.group_name);
+            let memory_key;
+            let group_name = if bench.kind == PlotKind::Memory {
+                memory_key = format!("{}{}", group_name, MEMORY_SUFFIX);
+                &memory_key
+            } else {
+                group_name
+            };
+            // Criterion reports NaN for some pathological runs, which has no place on an axis and
+            // would break the sort order of the stored line
+            if !bench.result.time.is_finite() {
+                warn!(
+                    "skipping {} `{}` of {}, whose value is {}",
+                    group_name, bench.id.params, bench.id.bench_name, bench.result.time
+                );
+                continue;
+            }
+            let (commit_date, sha) = match &config.head_commit {
+                Some(head) => (head.date, Some(head.sha.as_str())),
+                None => {
+                    // Stored data outlives any one run, so a single bad name shouldn't stop the rest
+                    // of the results from being added
+                    match parse_bench_name(&bench.id.bench_name) {
+                        Ok(commit) => (commit.date, commit.sha),
+                        Err(e) => {
+                            warn!("skipping {} `{}`: {}", group_name, bench.id.params, e);
+                            continue;
+                        }
+                    }
+                }
+            };
+            // Stored SHAs are short, like the ones in Criterion bench names
+            let sha = sha.map(|sha| short_sha(sha).to_owned());
+            if self.0.get(group_name).is_none() {
+                let plot = Plot {
+                    metric: config.metric,
+                    ..Plot::new(bench.kind)
+                };
+                self.0.insert(group_name.to_owned(), plot);
+            }
+            let plot = self.0.get_mut(group_name).unwrap();
+            // Rates and times of the same group can't share a plot, e.g. when the metric changes
+            // between runs
+            if plot.kind.higher_is_better() != bench.kind.higher_is_better() {
+                warn!(
+                    "skipping {} `{}`: the stored plot isn't of {}",
+                    group_name,
+                    bench.id.params,
+                    if bench.kind == PlotKind::Throughput {
+                        "throughput"
+                    } else {
+                        "times"
+                    }
+                );
+                continue;
+            }
+            if plot.kind == PlotKind::Time && plot.metric != config.metric {
+                warn!(
+                    "adding {} estimates of {} `{}` to a plot of {} estimates",
+                    config.metric.field(),
+                    group_name,
+                    bench.id.params,
+                    plot.metric.field()
+                );
+            }
+
+            // The unit Criterion reports takes precedence over one parsed from the bench ID
+            // The unit in a bench ID is that of the time, not the memory
+            let unit = bench.unit().or_else(|| {
+                config
+                    .unit_hint
+                    .as_ref()
+                    .filter(|_| bench.kind != PlotKind::Memory)
+                    .and_then(|hint| hint.parse(&bench.id))
+            });
+            // A plot keeps the first unit it sees, and later time or memory values are converted to it
+            let scale = match (plot.unit.as_deref(), unit) {
+                (None, Some(unit)) => {
+                    plot.unit = Some(unit.to_owned());
+                    1.0
+                }
+                (Some(plot_unit), Some(unit)) if plot_unit != unit => {
+                    match unit_scale(bench.kind, unit, plot_unit) {
+                        Some(scale) => scale,
+                        None => {
+                            warn!(
+                                "{} `{}` is in `{}` but the plot is in `{}`",
+                                group_name, bench.id.params, unit, plot_unit
+                            );
+                            1.0
+                        }
+                    }
+                }
+                _ => 1.0,
+            };
             let point = Point {
                 x: commit_date,
-                y: bench.result.time,
+                y: bench.result.time * scale,
+                interval: bench.result.interval.map(|i| Interval {
+                    lower: i.lower * scale,
+                    upper: i.upper * scale,
+                }),
+                sha,
+                unit: plot.unit.clone(),
             };
 
-            if self.0.get(&bench.id.group_name).is_none() {
-                self.0.insert(bench.id.group_name.to_owned(), Plot::new());
-            }
-            let plot = self.0.get_mut(&bench.id.group_name).unwrap();
-
             plot.x_axis.set_min_max(commit_date);
-            plot.y_axis.set_min_max(point.y);
 
             if plot.lines.get(&bench.id.params).is_none() {
                 plot.lines.insert(bench.id.params.to_owned(), vec![]);
             }
-            plot.lines.get_mut(&bench.id.params).unwrap().push(point);
+            let line = plot.lines.get_mut(&bench.id.params).unwrap();
+
+            // A point already stored for this commit is resolved by the conflict policy instead of
+            // stacking a second point on top of it
+            let y = point.y;
+            match add_point(
+                line,
+                point,
+                config.on_conflict,
+                group_name,
+                &bench.id.params,
+            )? {
+                Added::Inserted => plot.y_axis.set_min_max(y),
+                Added::Replaced => {
+                    replaced.insert(group_name.to_owned());
+                }
+                Added::Kept => continue,
+            }
+            changed += 1;
         }
-        // Sort each data point in each line for each plot
-        for plot in self.0.iter_mut() {
-            for line in plot.1.lines.iter_mut() {
-                line.1.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for group_name in replaced {
+            self.0.get_mut(&group_name).unwrap().recompute_axes();
+        }
+        Ok(changed)
+    }
+
+    // Combines `other`, e.g. the plot data of another CI shard, into these plots. Groups and lines
+    // missing here are added, and points for a commit that's already stored are resolved by
+    // `policy` like newly added results. Returns the number of points added or changed
+    pub fn merge(&mut self, other: Plots, policy: ConflictPolicy) -> Result<usize, Box<dyn Error>> {
+        let mut changed = 0;
+        for (group_name, other) in other.0 {
+            let Some(plot) = self.0.get_mut(&group_name) else {
+                changed += other.lines.values().map(Vec::len).sum::<usize>();
+                self.0.insert(group_name, other);
+                continue;
+            };
+            // Like in `add_data`, rates and times can't share a plot, e.g. when shards ran with
+            // different metrics
+            if plot.kind.higher_is_better() != other.kind.higher_is_better() {
+                warn!(
+                    "skipping {} of the merged plot data: the stored plot isn't of {}",
+                    group_name,
+                    if other.kind == PlotKind::Throughput {
+                        "throughput"
+                    } else {
+                        "times"
+                    }
+                );
+                continue;
+            }
+            // Each shard's plot keeps the first unit it saw, so the other's points are converted
+            let scale = match (plot.unit.as_deref(), other.unit.as_deref()) {
+                (None, Some(unit)) => {
+                    plot.unit = Some(unit.to_owned());
+                    1.0
+                }
+                (Some(plot_unit), Some(unit)) if plot_unit != unit => {
+                    unit_scale(plot.kind, unit, plot_unit).unwrap_or_else(|| {
+                        warn!(
+                            "merging {} in `{}` into a plot in `{}`",
+                            group_name, unit, plot_unit
+                        );
+                        1.0
+                    })
+                }
+                _ => 1.0,
+            };
+            for (params, points) in other.lines {
+                let line = plot.lines.entry(params.clone()).or_default();
+                for point in points {
+                    let point = Point {
+                        y: point.y * scale,
+                        interval: point.interval.map(|i| Interval {
+                            lower: i.lower * scale,
+                            upper: i.upper * scale,
+                        }),
+                        unit: plot.unit.clone(),
+                        ..point
+                    };
+                    if add_point(line, point, policy, &group_name, &params)? != Added::Kept {
+                        changed += 1;
+                    }
+                }
             }
+            plot.recompute_axes();
         }
+        Ok(changed)
+    }
+
+    // A copy without the first `n` points of each line, for rendering only, since the earliest
+    // results of a new benchmark are often unstable
+    pub fn skip_warmup(&self, n: usize) -> Plots {
+        self.retain_points(|points| points.get(n..).unwrap_or_default())
+    }
+
+    // A copy without the points older than `max_age` before the newest point of any plot, and
+    // with only the newest `max_points` of each line
+    pub fn prune(&self, config: &RetentionConfig) -> Plots {
+        let newest = self
+            .0
+            .values()
+            .flat_map(|plot| plot.lines.values())
+            .filter_map(|points| points.last())
+            .map(|p| p.x)
+            .max();
+        let cutoff = newest
+            .zip(config.max_age)
+            .and_then(|(newest, age)| newest.checked_sub_signed(age.0));
+        self.retain_points(|points| {
+            let points = match cutoff {
+                Some(cutoff) => &points[points.partition_point(|p| p.x < cutoff)..],
+                None => points,
+            };
+            let max = config.max_points.map_or(points.len(), NonZeroUsize::get);
+            &points[points.len().saturating_sub(max)..]
+        })
+    }
+
+    // A copy of only the groups `filter` includes, which are rendered and reported on while the
+    // others stay in storage
+    pub fn select(&self, filter: &GroupFilter) -> Plots {
+        let plots = self
+            .0
+            .iter()
+            .filter(|(group, _)| filter.includes(group))
+            .map(|(group, plot)| (group.clone(), plot.clone()))
+            .collect();
+        Plots(plots)
+    }
+
+    // A copy keeping the part of each line's sorted points selected by `keep`, with axes tightened
+    // around them. Lines and plots left empty are dropped
+    fn retain_points(&self, keep: impl Fn(&[Point]) -> &[Point]) -> Plots {
+        let plots = self
+            .0
+            .iter()
+            .filter_map(|(group, plot)| {
+                let lines = plot
+                    .lines
+                    .iter()
+                    .map(|(params, points)| (params, keep(points)))
+                    .filter(|(_, points)| !points.is_empty())
+                    .map(|(params, points)| (params.clone(), points.to_vec()))
+                    .collect::<BTreeMap<_, _>>();
+                if lines.is_empty() {
+                    return None;
+                }
+                let mut plot = Plot {
+                    lines,
+                    unit: plot.unit.clone(),
+                    metric: plot.metric,
+                    ..Plot::new(plot.kind)
+                };
+                plot.recompute_axes();
+                Some((group.clone(), plot))
+            })
+            .collect();
+        Plots(plots)
     }
 }
 
 // The data type for a plot: contains the range of X and Y values, and the line(s) to be drawn
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Plot {
-    x_axis: XAxisRange,
-    y_axis: YAxisRange,
-    lines: HashMap<String, Vec<Point>>,
+    // Defaults to `Time` for plot data saved before other metrics were supported
+    #[serde(default)]
+    pub(crate) kind: PlotKind,
+    // The axes are recomputed from the points on reading, so they may be missing
+    #[serde(default)]
+    pub(crate) x_axis: XAxisRange,
+    #[serde(default)]
+    pub(crate) y_axis: YAxisRange,
+    #[serde(default)]
+    pub(crate) lines: BTreeMap<String, Vec<Point>>,
+    // Unit of the values, if reported by Criterion or known from the bench ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) unit: Option<String>,
+    // Criterion estimate the times were read from, `typical` for plot data saved before it was
+    // selectable
+    #[serde(default)]
+    pub(crate) metric: Metric,
 }
 
 impl Plot {
-    pub fn new() -> Self {
+    pub fn new(kind: PlotKind) -> Self {
         Self {
+            kind,
             x_axis: XAxisRange::default(),
             y_axis: YAxisRange::default(),
-            lines: HashMap::new(),
+            lines: BTreeMap::new(),
+            unit: None,
+            metric: Metric::default(),
+        }
+    }
+
+    // Description of the plotted values, used for axis labels
+    pub fn value_desc(&self) -> String {
+        match (self.kind, &self.unit) {
+            (PlotKind::Count, None) => "Count".to_owned(),
+            (PlotKind::Count, Some(unit)) => format!("Count ({})", unit),
+            (PlotKind::Memory, None) => "Memory (bytes)".to_owned(),
+            (PlotKind::Memory, Some(unit)) => format!("Memory ({})", unit),
+            (PlotKind::Throughput, unit) => {
+                format!("Throughput ({})", unit.as_deref().unwrap_or("per second"))
+            }
+            (_, None) => format!("Time (ns, {})", self.metric.field()),
+            // Units from bench IDs aren't necessarily times, e.g. `bytes`
+            (_, Some(unit)) if ns_per(unit).is_some() => {
+                format!("Time ({}, {})", unit, self.metric.field())
+            }
+            (_, Some(unit)) => format!("Value ({}, {})", unit, self.metric.field()),
+        }
+    }
+
+    // Axis description and the factor stored values are divided by when rendered. Times are shown
+    // in whichever of ns, µs, ms or s suits the plot's largest value, e.g. `2.5` s rather than
+    // `2500000000` ns, memory likewise in B, kB, MB or GB and throughput in B/s to GB/s or elem/s
+    // to Gelem/s, while other values are shown as stored. Memory uses decimal units since the axis
+    // ticks fall on round byte counts
+    pub fn display_scale(&self) -> (String, f64) {
+        match (self.kind, self.display_unit()) {
+            (PlotKind::Memory, Some((unit, divisor))) => (format!("Memory ({})", unit), divisor),
+            (PlotKind::Throughput, Some((unit, divisor))) => {
+                (format!("Throughput ({})", unit), divisor)
+            }
+            (_, Some((unit, divisor))) => {
+                (format!("Time ({}, {})", unit, self.metric.field()), divisor)
+            }
+            (_, None) => (self.value_desc(), 1.0),
+        }
+    }
+
+    // The unit values are shown in and the factor they're divided by, or `None` if the plot isn't
+    // of times or memory in a known unit
+    pub fn display_unit(&self) -> Option<(&'static str, f64)> {
+        self.unit_for(self.y_axis.max)
+    }
+
+    // The unit `value` is best shown in, i.e. the largest it's at least one of, and the factor it's
+    // divided by, or `None` if the plot isn't of times or memory in a known unit
+    pub fn unit_for(&self, value: f64) -> Option<(&'static str, f64)> {
+        let (stored, units, smallest) = match (self.kind, self.unit.as_deref()) {
+            (PlotKind::Count, _) => None,
+            (PlotKind::Memory, unit) => Some((
+                unit.map_or(Some(1.0), bytes_per)?,
+                [("GB", 1e9), ("MB", 1e6), ("kB", 1e3)],
+                "B",
+            )),
+            (PlotKind::Throughput, Some("B/s")) => {
+                Some((1.0, [("GB/s", 1e9), ("MB/s", 1e6), ("kB/s", 1e3)], "B/s"))
+            }
+            (PlotKind::Throughput, Some("elem/s")) => Some((
+                1.0,
+                [("Gelem/s", 1e9), ("Melem/s", 1e6), ("Kelem/s", 1e3)],
+                "elem/s",
+            )),
+            (PlotKind::Throughput, _) => None,
+            (_, unit) => Some((
+                unit.map_or(Some(1.0), ns_per)?,
+                [("s", 1e9), ("ms", 1e6), ("µs", 1e3)],
+                "ns",
+            )),
+        }?;
+        let value = value.abs() * stored;
+        let (unit, base) = units
+            .into_iter()
+            .find(|(_, base)| value >= *base)
+            .unwrap_or((smallest, 1.0));
+        Some((unit, base / stored))
+    }
+
+    // Sorts each line's points, and recomputes the axes since conflicting points may have
+    // replaced a previous min or max
+    fn sort_points(&mut self) {
+        for line in self.lines.values_mut() {
+            line.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        }
+        self.recompute_axes();
+    }
+
+    // Resets the X and Y ranges to span exactly the current points
+    fn recompute_axes(&mut self) {
+        self.x_axis = XAxisRange::default();
+        self.y_axis = YAxisRange::default();
+        for point in self.lines.values().flatten() {
+            self.x_axis.set_min_max(point.x);
+            self.y_axis.set_min_max(point.y);
         }
     }
 }
 
+// The metric a plot tracks, which determines its Y axis units and formatting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlotKind {
+    // Benchmark time in nanoseconds
+    #[default]
+    Time,
+    // Plain integer metric, e.g. a circuit's constraint count
+    Count,
+    // Peak memory in bytes, from the `memory` estimate next to the time. Stored as its own plot,
+    // keyed by the group name with `MEMORY_SUFFIX`
+    Memory,
+    // Bytes or elements per second, with the unit `B/s` or `elem/s`
+    Throughput,
+}
+
+impl PlotKind {
+    // Whether a rise in value is an improvement, so a fall is the regression
+    pub fn higher_is_better(self) -> bool {
+        self == PlotKind::Throughput
+    }
+}
+
 // Historical benchmark result, showing the performance at a given Git commit
-#[derive(Debug, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 pub struct Point {
     // Commit timestamp associated with benchmark
-    x: DateTime<Utc>,
+    pub(crate) x: DateTime<Utc>,
     // Benchmark time (avg.)
-    y: f64,
+    pub(crate) y: f64,
+    // Confidence interval of `y`, if the benchmark reported one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) interval: Option<Interval>,
+    // Short SHA of the commit, missing from plot data saved before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) sha: Option<String>,
+    // Unit of `y` if known, e.g. `ns` or a custom Criterion measurement's unit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) unit: Option<String>,
 }
 
-// Min. and max. X axis values for a given plot
-#[derive(Debug, Serialize, Deserialize)]
-pub struct XAxisRange {
-    #[serde(with = "ts_seconds")]
-    min: DateTime<Utc>,
-    #[serde(with = "ts_seconds")]
-    max: DateTime<Utc>,
+// What `add_point` did with a point
+#[derive(Debug, PartialEq)]
+enum Added {
+    Inserted,
+    Replaced,
+    Kept,
 }
 
-// Starts with flipped min/max so they can be set by `Point` values as they are encountered
-impl Default for XAxisRange {
-    fn default() -> Self {
-        Self {
-            min: Utc::now(),
-            max: chrono::DateTime::<Utc>::MIN_UTC,
+// Adds `point` to `line`, which is sorted by commit date and keeps one point per commit. A new
+// commit's point is inserted in place, so a run costs a search per new point rather than a sort of
+// the whole history
+fn add_point(
+    line: &mut Vec<Point>,
+    point: Point,
+    policy: ConflictPolicy,
+    group_name: &str,
+    params: &str,
+) -> Result<Added, Box<dyn Error>> {
+    let at = line.partition_point(|p| p.x < point.x);
+    let existing = match line.get_mut(at) {
+        Some(existing) if existing.x == point.x => existing,
+        _ => {
+            line.insert(at, point);
+            return Ok(Added::Inserted);
+        }
+    };
+    let replace = match policy {
+        ConflictPolicy::KeepExisting => false,
+        ConflictPolicy::Overwrite => point != *existing,
+        ConflictPolicy::KeepMin => point.y < existing.y,
+        ConflictPolicy::Error if point.y != existing.y => {
+            return Err(format!(
+                "Conflicting values for {} `{}` at {}: stored {}, new {}",
+                group_name, params, point.x, existing.y, point.y
+            )
+            .into());
         }
+        ConflictPolicy::Error => false,
+    };
+    if !replace {
+        return Ok(Added::Kept);
+    }
+    *existing = point;
+    Ok(Added::Replaced)
+}
+
+// Factor converting values of a time or memory plot in `from` to `to`, or `None` if either isn't a
+// known unit. Bytes and elements per second don't convert to each other
+fn unit_scale(kind: PlotKind, from: &str, to: &str) -> Option<f64> {
+    let per = match kind {
+        PlotKind::Memory => bytes_per,
+        PlotKind::Throughput => return None,
+        _ => ns_per,
+    };
+    Some(per(from)? / per(to)?)
+}
+
+// Bytes per one of a memory unit, or `None` if `unit` isn't a known memory unit
+pub(crate) fn bytes_per(unit: &str) -> Option<f64> {
+    match unit {
+        "B" | "bytes" => Some(1.0),
+        "kB" => Some(1e3),
+        "MB" => Some(1e6),
+        "GB" => Some(1e9),
+        "KiB" => Some(1024.0),
+        "MiB" => Some(1024f64.powi(2)),
+        "GiB" => Some(1024f64.powi(3)),
+        _ => None,
+    }
+}
+
+// Nanoseconds per one of a time unit, or `None` if `unit` isn't a known time unit
+pub(crate) fn ns_per(unit: &str) -> Option<f64> {
+    match unit {
+        "ns" => Some(1.0),
+        "us" | "µs" => Some(1e3),
+        "ms" => Some(1e6),
+        "s" => Some(1e9),
+        _ => None,
+    }
+}
+
+// Min. and max. X axis values for a given plot, unset until a `Point` is encountered
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct XAxisRange {
+    #[serde(default, with = "ts_seconds_option")]
+    min: Option<DateTime<Utc>>,
+    #[serde(default, with = "ts_seconds_option")]
+    max: Option<DateTime<Utc>>,
+}
+
+impl XAxisRange {
+    // The min. and max. dates, or `None` if no point was added. Plot data saved before the range
+    // could be unset stored an empty range as min > max, so that counts as unset too
+    pub fn bounds(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let (min, max) = self.min.zip(self.max)?;
+        (min <= max).then_some((min, max))
     }
 }
 
 // Min. and max. Y axis values for a given plot
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct YAxisRange {
     min: f64,
     max: f64,
@@ -214,11 +2512,15 @@ trait MinMax<T: PartialOrd> {
 
 impl MinMax<DateTime<Utc>> for XAxisRange {
     fn set_min_max(&mut self, value: DateTime<Utc>) {
-        if value < self.min {
-            self.min = value
-        }
-        if value > self.max {
-            self.max = value
+        match self.bounds() {
+            Some((min, max)) => {
+                self.min = Some(min.min(value));
+                self.max = Some(max.max(value));
+            }
+            None => {
+                self.min = Some(value);
+                self.max = Some(value);
+            }
         }
     }
 }
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::plot::{bytes_per, ns_per, Plot, PlotKind, Plots, MEMORY_SUFFIX};
+
+// Name of the kind of values `plot` holds, e.g. `time_ns`, and the factor converting them to that
+// unit: times to nanoseconds and memory to bytes where the unit is known, while other values, e.g.
+// throughput in bytes or elements per second, are kept as stored
+pub fn value_name(plot: &Plot) -> (&'static str, f64) {
+    let (name, per): (_, fn(&str) -> Option<f64>) = match plot.kind {
+        PlotKind::Memory => ("memory_bytes", bytes_per),
+        PlotKind::Count => ("count", |_| None),
+        PlotKind::Throughput if plot.unit.as_deref() == Some("B/s") => {
+            ("throughput_bytes_per_second", |_| None)
+        }
+        PlotKind::Throughput => ("throughput_elements_per_second", |_| None),
+        PlotKind::Time => ("time_ns", ns_per),
+    };
+    let scale = plot.unit.as_deref().map_or(Some(1.0), per).unwrap_or(1.0);
+    (name, scale)
+}
+
+// The group a plot's values are labeled with: memory is labeled with the group it was measured
+// with, like the time
+pub fn measured_group(group: &str) -> &str {
+    group.strip_suffix(MEMORY_SUFFIX).unwrap_or(group)
+}
+
+// Latest value of every params line in Prometheus' text format, for the node exporter's textfile
+// collector. Only the newest point is written since Prometheus keeps the history itself, as one
+// gauge per kind of plot named by `value_name`, e.g. `benchmark_time_ns`
+pub fn prometheus_metrics(plots: &Plots) -> String {
+    // Metric name -> samples, since each metric's samples have to follow its `TYPE` line
+    let mut metrics = BTreeMap::<String, Vec<String>>::new();
+    for (group, plot) in plots.0.iter() {
+        let (name, scale) = value_name(plot);
+        let name = format!("benchmark_{}", name);
+        let group = measured_group(group);
+        for (params, points) in plot.lines.iter() {
+            let Some(latest) = points.last() else {
+                continue;
+            };
+            metrics.entry(name.clone()).or_default().push(format!(
+                "{}{{group=\"{}\",params=\"{}\"}} {}",
+                name,
+                label_value(group),
+                label_value(params),
+                latest.y * scale
+            ));
+        }
+    }
+
+    let mut text = String::new();
+    for (name, samples) in metrics {
+        writeln!(text, "# TYPE {} gauge", name).unwrap();
+        for sample in samples {
+            writeln!(text, "{}", sample).unwrap();
+        }
+    }
+    text
+}
+
+// Escapes a label value as the text format requires, replacing other control characters, which
+// scrapers reject, with `_`
+fn label_value(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => escaped.push('_'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,694 @@
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Write as _},
+    io::{self, Read, Write},
+    time::Instant,
+};
+
+use anyhow::{anyhow, Context};
+use camino::{Utf8Path, Utf8PathBuf};
+use rayon::prelude::*;
+#[cfg(feature = "http")]
+use tracing::error;
+use tracing::{debug, info, warn};
+
+use crate::analysis::{
+    compare, param_set_changes, regressions, stability_ranking, timestamp_anomalies, Regression,
+};
+use crate::badge::badge_svg;
+use crate::config::Config;
+use crate::csv::plots_csv;
+use crate::git::{commit_history, commit_subjects, head_commit, CommitInfo};
+#[cfg(feature = "http")]
+use crate::github;
+use crate::html::html_report;
+#[cfg(feature = "http")]
+use crate::influx;
+use crate::influx::{is_url, line_protocol};
+use crate::json::{
+    read_json_from_file, read_json_from_reader, validate_file, validate_reader, BenchData,
+    Extractor, JsonError,
+};
+use crate::locale;
+use crate::markdown::{comparison_table, head_to_head_table};
+use crate::plot::{generate_comparisons, generate_plots, short_sha, Plots};
+use crate::prometheus::prometheus_metrics;
+use crate::sink::FileSink;
+#[cfg(feature = "http")]
+use crate::slack;
+
+// Gets all JSON paths in `dir`, optionally ending in a given suffix, along with their gzipped
+// `.gz` versions. E.g. if `suffix` is `abc1234.json` it will return "*abc1234.json{,.gz}"
+// Entries with non-UTF-8 names can't be bench files, so they're skipped with a warning. Paths are
+// sorted, so duplicate results across files are resolved the same way on every machine
+fn get_json_paths(dir: &Utf8Path, suffix: Option<&str>) -> io::Result<Vec<Utf8PathBuf>> {
+    let suffix = suffix.unwrap_or(".json");
+    let gz_suffix = format!("{}.gz", suffix);
+    let mut paths = vec![];
+    for entry in dir.read_dir_utf8()? {
+        match entry {
+            Ok(entry)
+                if [suffix, &gz_suffix]
+                    .iter()
+                    .any(|s| entry.path().as_str().ends_with(s)) =>
+            {
+                paths.push(entry.into_path());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("skipping an entry of {}: {}", dir, e),
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+// Gets all paths in `dir` matching `pattern`, in sorted order. `**` matches any number of
+// subdirectories
+fn glob_json_paths(dir: &Utf8Path, pattern: &glob::Pattern) -> anyhow::Result<Vec<Utf8PathBuf>> {
+    let pattern = format!("{}/{}", glob::Pattern::escape(dir.as_str()), pattern);
+    let mut paths = vec![];
+    for entry in glob::glob(&pattern)? {
+        match entry.map_err(anyhow::Error::from).and_then(|path| {
+            Utf8PathBuf::from_path_buf(path)
+                .map_err(|path| anyhow!("{} isn't valid UTF-8", path.display()))
+        }) {
+            Ok(path) => paths.push(path),
+            Err(e) => warn!("skipping a match of {}: {}", pattern, e),
+        }
+    }
+    Ok(paths)
+}
+
+// Name of the file in the output directory that stores all plot data between runs
+const PLOT_DATA_FILE: &str = "plot-data.json";
+
+// Deserializes JSON file in `dir` into `Plots` type, or `None` if there isn't one yet. A file that
+// can't be read is an error rather than `None`, so its history isn't overwritten by a fresh file
+fn read_plots_from_file(dir: &Utf8Path) -> anyhow::Result<Option<Plots>> {
+    let path = dir.join(PLOT_DATA_FILE);
+    match read_plots(&path) {
+        Err(e)
+            if e.downcast_ref::<io::Error>().map(io::Error::kind)
+                == Some(io::ErrorKind::NotFound) =>
+        {
+            Ok(None)
+        }
+        result => result
+            .map(Some)
+            .with_context(|| format!("Failed to read plot data {}", path)),
+    }
+}
+
+// Deserializes the plot data file at `path`, e.g. a `plot-data.json` from another CI shard
+fn read_plots(path: &Utf8Path) -> anyhow::Result<Plots> {
+    let mut file = std::fs::File::open(path)?;
+
+    let mut s = String::new();
+    file.read_to_string(&mut s)?;
+
+    Plots::from_json(&s).map_err(|e| anyhow!("{}", e))
+}
+
+// Serializes `Plots` type into file in `dir`, creating it if needed
+fn write_plots_to_file(dir: &Utf8Path, plot_data: &Plots) -> Result<(), io::Error> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(PLOT_DATA_FILE);
+
+    let mut file = std::fs::File::create(path)?;
+
+    let json_data = plot_data.to_json()?;
+
+    file.write_all(json_data.as_bytes())
+}
+
+// Name of the file in the output directory that lists the regressions found in the latest results
+const REGRESSIONS_FILE: &str = "regressions.json";
+
+// Appends the results read from `source`, warning about records or files that were skipped.
+// Returns a description of each
+fn add_bench_data(
+    bench_data: &mut Vec<BenchData>,
+    source: impl fmt::Display,
+    result: anyhow::Result<(Vec<BenchData>, Vec<JsonError>)>,
+) -> Vec<String> {
+    match result {
+        Ok((mut data, errors)) => {
+            debug!("read {} results from {}", data.len(), source);
+            for e in &errors {
+                debug!("skipped an entry of {}: {}", source, e);
+            }
+            if !errors.is_empty() {
+                warn!("skipped {} malformed entries in {}", errors.len(), source);
+            }
+            bench_data.append(&mut data);
+            errors
+                .iter()
+                .map(|e| format!("{}: {}", source, e))
+                .collect()
+        }
+        Err(e) => {
+            warn!("skipping bench data: {e:#}");
+            vec![format!("{e:#}")]
+        }
+    }
+}
+
+// What a `--dry-run` would have written: the files read, the entries skipped, the size of each
+// group and the regressions found
+fn dry_run_summary(
+    sources: &[String],
+    skipped: &[String],
+    plots: &Plots,
+    changed: usize,
+    regressions: &[Regression],
+) -> String {
+    let mut summary = String::from("Dry run, so no files were written\n");
+    let mut section = |title: &str, lines: &mut dyn Iterator<Item = String>| {
+        writeln!(summary, "{}:", title).unwrap();
+        let mut empty = true;
+        for line in lines {
+            writeln!(summary, "  {}", line).unwrap();
+            empty = false;
+        }
+        if empty {
+            writeln!(summary, "  none").unwrap();
+        }
+    };
+    section("Bench files read", &mut sources.iter().cloned());
+    section("Skipped entries", &mut skipped.iter().cloned());
+    section(
+        "Groups",
+        &mut plots.0.iter().map(|(group, plot)| {
+            let points = plot.lines.values().map(Vec::len).sum::<usize>();
+            format!("{}: {} lines, {} points", group, plot.lines.len(), points)
+        }),
+    );
+    section(
+        "Regressions from the previous commit",
+        &mut regressions.iter().map(ToString::to_string),
+    );
+    writeln!(summary, "{} points would be added or changed", changed).unwrap();
+    summary
+}
+
+// Value Vergen emits in place of Git info it couldn't read at build time, e.g. outside a checkout
+const VERGEN_PLACEHOLDER: &str = "VERGEN_IDEMPOTENT_OUTPUT";
+
+// SHA of the commit being plotted: the given override, the resolved `HEAD`, or the commit the
+// binary was built at, in that order. `None` if the build had no Git info either
+fn commit_sha(flag: Option<&str>, head: Option<&CommitInfo>) -> Option<String> {
+    let built = option_env!("VERGEN_GIT_SHA").filter(|sha| {
+        !sha.is_empty() && *sha != VERGEN_PLACEHOLDER && sha.chars().all(|c| c.is_ascii_hexdigit())
+    });
+    flag.or(head.map(|head| head.sha.as_str()))
+        .or(built)
+        .map(str::to_owned)
+}
+
+// Counts of what a `run` did, for callers to report or act on
+#[derive(Debug, Default)]
+pub struct RunSummary {
+    // Groups whose charts were written, none on a dry run or when nothing changed
+    pub groups_rendered: Vec<String>,
+    // Points added to or changed in the plot data
+    pub points_added: usize,
+    // Each regression from the previous commit, as printed
+    pub regressions: Vec<String>,
+    // Whether the run should fail a CI job: a bench file over `max_reject_rate` when validating,
+    // or a regression found with `regression.fail` set
+    pub failed: bool,
+}
+
+// What to read and write on a run. Set from the command line, since they differ between runs
+// rather than between repositories
+#[derive(Debug)]
+pub struct RunOptions {
+    // Rewrite the plot data and charts even if no points changed
+    pub force: bool,
+    // Read benchmark JSON from stdin instead of bench files
+    pub stdin: bool,
+    // Print what would be written, without writing anything
+    pub dry_run: bool,
+    // Only check the bench files' format, failing if more than `max_reject_rate` of a file's
+    // records are rejected
+    pub validate: bool,
+    pub max_reject_rate: f64,
+    // Bench files to add, without the `.json` extension. Defaults to every file ending in the
+    // current commit's short SHA
+    pub bench_files: Vec<String>,
+    // Add the bench files matching this instead of `bench_files`
+    pub glob: Option<glob::Pattern>,
+    // `plot-data.json` files to combine instead of adding bench files
+    pub merge: Vec<Utf8PathBuf>,
+    // SHA of the commit being plotted, overriding `HEAD`
+    pub sha: Option<String>,
+    pub input_dir: Utf8PathBuf,
+    pub export_csv: Option<Utf8PathBuf>,
+    pub export_markdown: Option<Utf8PathBuf>,
+    pub prometheus: Option<Utf8PathBuf>,
+    // File to write the points to in InfluxDB's line protocol, or a write URL to post them to
+    pub influx: Option<String>,
+    pub html: Option<Utf8PathBuf>,
+    // Line to make a badge of, e.g. `Fibonacci-num=10/rc=100`, and the file to write it to
+    pub badge: Option<(String, Utf8PathBuf)>,
+    // Base and head SHAs to compare head-to-head instead of plotting the history
+    pub compare: Vec<String>,
+    // Repository to look up commit messages in
+    pub git_dir: Option<Utf8PathBuf>,
+    #[cfg(feature = "http")]
+    pub slack_webhook: Option<String>,
+    #[cfg(feature = "http")]
+    pub plot_url: Option<String>,
+    #[cfg(feature = "http")]
+    pub influx_token: Option<String>,
+    #[cfg(feature = "http")]
+    pub github: Option<github::PullRequest>,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            force: false,
+            stdin: false,
+            dry_run: false,
+            validate: false,
+            max_reject_rate: 0.0,
+            bench_files: vec![],
+            glob: None,
+            merge: vec![],
+            sha: None,
+            input_dir: Utf8PathBuf::from("."),
+            export_csv: None,
+            export_markdown: None,
+            prometheus: None,
+            influx: None,
+            html: None,
+            badge: None,
+            compare: vec![],
+            git_dir: None,
+            #[cfg(feature = "http")]
+            slack_webhook: None,
+            #[cfg(feature = "http")]
+            plot_url: None,
+            #[cfg(feature = "http")]
+            influx_token: None,
+            #[cfg(feature = "http")]
+            github: None,
+        }
+    }
+}
+
+// Reads the stored plot data and the bench results `config.run` selects, adds them, then reports
+// regressions and writes the plot data, charts and any exports the config asks for
+pub fn run(mut config: Config) -> anyhow::Result<RunSummary> {
+    let RunOptions {
+        force,
+        stdin,
+        dry_run,
+        validate,
+        max_reject_rate,
+        bench_files,
+        glob,
+        merge,
+        sha,
+        input_dir,
+        export_csv,
+        export_markdown,
+        prometheus,
+        influx,
+        html,
+        badge,
+        compare: compare_shas,
+        git_dir,
+        #[cfg(feature = "http")]
+        slack_webhook,
+        #[cfg(feature = "http")]
+        plot_url,
+        #[cfg(feature = "http")]
+        influx_token,
+        #[cfg(feature = "http")]
+        github,
+    } = std::mem::take(&mut config.run);
+    let output_dir = Utf8PathBuf::from(config.output_dir.as_deref().unwrap_or("."));
+    locale::set(config.locale.clone());
+    config.chart.regression = config.regression.clone();
+    if config.ingest.merge_group_aliases {
+        config.ingest.group_aliases = config.chart.group_aliases.clone();
+    }
+    if config.ingest.stamp_head {
+        match head_commit() {
+            Ok(commit) => config.ingest.head_commit = Some(commit),
+            Err(e) => warn!("falling back to dates from bench names: {e:#}"),
+        }
+    }
+    // Prefer the runtime `HEAD` if it was resolved, so the files match the stamped date
+    let short_sha = commit_sha(sha.as_deref(), config.ingest.head_commit.as_ref())
+        .map(|sha| short_sha(&sha).to_owned());
+    config.chart.commit_sha = short_sha.clone().unwrap_or_default();
+    let extractor = config
+        .ingest
+        .count_pointer
+        .clone()
+        .map_or(Extractor::Time(config.ingest.metric), Extractor::Count);
+    let id_format = &config.ingest.bench_id;
+
+    // If existing plot data is found on disk, only read and add benchmark files given by `--bench-files`
+    // Points are keyed by commit so duplicates are ignored
+    let mut changed = 0;
+    let stored = || read_plots_from_file(&output_dir);
+    let (mut plots, bench_files) = {
+        // Shards' plot data is combined into new plots, which replace the stored ones
+        if !merge.is_empty() {
+            let mut plots = Plots::new();
+            for path in &merge {
+                let shard = read_plots(path)
+                    .with_context(|| format!("Failed to read plot data {}", path))?;
+                changed += plots
+                    .merge(shard, config.ingest.on_conflict)
+                    .map_err(|e| anyhow!("Failed to merge {}: {e}", path))?;
+            }
+            (plots, vec![])
+        }
+        // Piped results are added instead of any bench files
+        else if stdin {
+            let plots = stored()?.unwrap_or_else(Plots::new);
+            (plots, vec![])
+        }
+        // A glob selects the files explicitly, whether or not plot data exists
+        else if let Some(pattern) = glob {
+            let plots = stored()?.unwrap_or_else(Plots::new);
+            let paths =
+                glob_json_paths(&input_dir, &pattern).context("Failed to read JSON paths")?;
+            (plots, paths)
+        } else if let Some(plots) = stored()? {
+            // The user should know which files they just benchmarked and want to add to the plot
+            // Otherwise defaults to all files containing the current Git commit, or every file if
+            // it isn't known
+            let bench_files = if bench_files.is_empty() {
+                let suffix = short_sha.as_ref().map(|sha| format!("{}.json", sha));
+                if suffix.is_none() {
+                    warn!("no commit SHA is known, so adding every bench file; pass `--sha` to select the current commit's");
+                }
+                get_json_paths(&input_dir, suffix.as_deref())
+                    .context("Failed to read JSON paths")?
+            } else {
+                // Falls back to an archived `.json.gz` if there's no plain file
+                bench_files
+                    .iter()
+                    .map(|file| {
+                        let path = input_dir.join(format!("{}.json", file));
+                        let gz_path = input_dir.join(format!("{}.json.gz", file));
+                        if !path.exists() && gz_path.exists() {
+                            gz_path
+                        } else {
+                            path
+                        }
+                    })
+                    .collect()
+            };
+            (plots, bench_files)
+        }
+        // If no plot data exists, read all `JSON` files in the input directory and save to disk
+        else {
+            let paths = get_json_paths(&input_dir, None).context("Failed to read JSON paths")?;
+            (Plots::new(), paths)
+        }
+    };
+    if !stdin && merge.is_empty() {
+        info!("adding bench files to plot: {:?}", bench_files);
+    }
+    // Catches format changes in CI before plots go empty, without touching the plot data
+    if validate {
+        let mut results = bench_files
+            .par_iter()
+            .map(|file| (file.to_string(), validate_file(file, &extractor, id_format)))
+            .collect::<Vec<_>>();
+        if stdin {
+            let result = validate_reader(io::stdin().lock(), &extractor, id_format);
+            results.push(("stdin".to_owned(), result));
+        }
+        let mut failed = false;
+        for (source, result) in results {
+            let validation = match result {
+                Ok(validation) => validation,
+                Err(e) => {
+                    println!("{}: {e:#}", source);
+                    failed = true;
+                    continue;
+                }
+            };
+            let reject_rate = validation.reject_rate();
+            println!(
+                "{}: {} matched, {} rejected ({}%)",
+                source,
+                validation.matched,
+                validation.rejected(),
+                locale::current().fixed(reject_rate * 100.0, 1)
+            );
+            for e in &validation.errors {
+                println!("  {}", e);
+            }
+            if validation.incomplete > 0 {
+                println!(
+                    "  {} without {}",
+                    validation.incomplete,
+                    extractor.expected()
+                );
+            }
+            failed |= reject_rate > max_reject_rate;
+        }
+        return Ok(RunSummary {
+            failed,
+            ..RunSummary::default()
+        });
+    }
+    let start = Instant::now();
+    let mut bench_data = vec![];
+    // Files are read in parallel, since runs with a file per commit are I/O bound, but collected in
+    // order, which decides conflicts between results for the same commit
+    let results = bench_files
+        .par_iter()
+        .map(|file| (file, read_json_from_file(file, &extractor, id_format)))
+        .collect::<Vec<_>>();
+    // One unreadable file, e.g. a truncated upload, shouldn't stop the others from being plotted
+    let mut skipped = vec![];
+    for (file, result) in results {
+        skipped.extend(add_bench_data(&mut bench_data, file, result));
+    }
+    if stdin {
+        let result = read_json_from_reader(io::stdin().lock(), &extractor, id_format)
+            .context("Failed to read stdin");
+        skipped.extend(add_bench_data(&mut bench_data, "stdin", result));
+    }
+    debug!("read bench data in {:?}", start.elapsed());
+    changed += plots
+        .add_data(&bench_data, &config.ingest)
+        .map_err(|e| anyhow!("Failed to add benchmark data: {e}"))?;
+    // Groups left out by `--only` and `--exclude` are still stored, but not rendered or reported on
+    let selected = config
+        .groups
+        .is_enabled()
+        .then(|| plots.select(&config.groups));
+    let shown = selected.as_ref().unwrap_or(&plots);
+    if dry_run {
+        let mut sources = merge
+            .iter()
+            .chain(&bench_files)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if stdin {
+            sources.push("stdin".to_owned());
+        }
+        let regressions = regressions(shown, &config.regression);
+        print!(
+            "{}",
+            dry_run_summary(&sources, &skipped, shown, changed, &regressions)
+        );
+        return Ok(RunSummary {
+            points_added: changed,
+            regressions: regressions.iter().map(ToString::to_string).collect(),
+            ..RunSummary::default()
+        });
+    }
+    if let Some(dir) = &git_dir {
+        let shas = shown
+            .0
+            .values()
+            .flat_map(|plot| plot.lines.values().flatten())
+            .filter_map(|point| point.sha.as_deref())
+            .collect::<BTreeSet<_>>();
+        config.chart.commit_messages = commit_subjects(dir, shas);
+    }
+    // Exported even without changes, since the files are requested explicitly
+    let exports = [
+        (export_csv, plots_csv as fn(&Plots) -> String),
+        (export_markdown, comparison_table),
+        (prometheus, prometheus_metrics),
+    ];
+    for (path, export) in exports {
+        if let Some(path) = path {
+            std::fs::write(&path, export(shown))
+                .with_context(|| format!("Failed to write {}", path))?;
+        }
+    }
+    // A failed push is only reported, like the other web integrations
+    if let Some(target) = &influx {
+        let points = line_protocol(shown);
+        if !is_url(target) {
+            std::fs::write(target, points)
+                .with_context(|| format!("Failed to write {}", target))?;
+        } else {
+            #[cfg(feature = "http")]
+            if let Err(e) = influx::push(target, points, influx_token.as_deref()) {
+                error!("failed to push the points to InfluxDB: {e:#}");
+            }
+            #[cfg(not(feature = "http"))]
+            return Err(anyhow!(
+                "Posting to {} needs the `http` feature, so pass a file to write instead",
+                target
+            ));
+        }
+    }
+    if let Some(path) = &html {
+        std::fs::write(path, html_report(shown, &config.chart))
+            .with_context(|| format!("Failed to write {}", path))?;
+    }
+    // Params can contain `/`, but group names can't, since they're the first segment of bench IDs
+    if let Some((line, path)) = &badge {
+        let (group, params) = line.split_once('/').unwrap_or((line, ""));
+        let alias = config.chart.group_aliases.resolve(group);
+        let label = if params.is_empty() {
+            alias.to_owned()
+        } else {
+            format!("{} {}", alias, params)
+        };
+        let svg = plots
+            .0
+            .get(group)
+            .and_then(|plot| badge_svg(&label, plot, params, &config.regression))
+            .ok_or_else(|| anyhow!("No line `{}` to make a badge of", line))?;
+        std::fs::write(path, svg).with_context(|| format!("Failed to write {}", path))?;
+    }
+    // A failed post is only reported, since the plot data and charts are still worth saving
+    #[cfg(feature = "http")]
+    if let Some(pr) = &github {
+        match github::upsert_comment(pr, &comparison_table(shown)) {
+            Ok(url) => println!("Comment has been posted to {}", url),
+            Err(e) => error!("failed to comment on {} #{}: {e:#}", pr.repo, pr.number),
+        }
+    }
+    if let [base, head] = compare_shas.as_slice() {
+        let comparisons = compare(shown, base, head)?;
+        if comparisons.is_empty() {
+            return Err(anyhow!(
+                "No benchmark has results at both {} and {}",
+                base,
+                head
+            ));
+        }
+        print!("{}", head_to_head_table(shown, &comparisons, base, head));
+        generate_comparisons(
+            shown,
+            &comparisons,
+            (base, head),
+            &config.chart,
+            &mut FileSink::new(&output_dir),
+        )
+        .map_err(|e| anyhow!("Failed to render the comparison: {e}"))?;
+        // Nothing is stored, so no points count as added
+        return Ok(RunSummary::default());
+    }
+    // Avoid a spurious diff of `plot-data.json` and re-rendering identical charts, e.g. on a
+    // commit that didn't run any benchmarks
+    if changed == 0 && !force {
+        info!("no changes");
+        return Ok(RunSummary::default());
+    }
+    info!("{} points added or changed", changed);
+    let start = Instant::now();
+
+    let param_changes = param_set_changes(shown);
+    if !param_changes.is_empty() {
+        println!("Params that start or stop partway through their group's history:");
+        for change in param_changes {
+            println!("  {}", change);
+        }
+    }
+
+    // Git history is only used to improve the check, so it's fine if it can't be read
+    let history = commit_history().ok();
+    let anomalies = timestamp_anomalies(shown, history.as_deref());
+    if !anomalies.is_empty() {
+        println!("Commit dates that disagree with commit order:");
+        for anomaly in anomalies {
+            println!("  {}", anomaly);
+        }
+    }
+    config.chart.history = history;
+
+    let regressions = regressions(shown, &config.regression);
+    if !regressions.is_empty() {
+        println!("Regressions from the previous commit:");
+        for regression in regressions.iter() {
+            println!("  {}", regression);
+        }
+    }
+    // Written even when empty, so CI doesn't pick up a stale report from an earlier run
+    std::fs::create_dir_all(&output_dir)
+        .and_then(|()| {
+            let report = serde_json::to_string_pretty(&regressions)?;
+            std::fs::write(output_dir.join(REGRESSIONS_FILE), report)
+        })
+        .context("Failed to write `regressions.json`")?;
+    debug!("checked for regressions in {:?}", start.elapsed());
+
+    if config.report.stability {
+        println!("Benchmark stability, noisiest first:");
+        for line in stability_ranking(shown) {
+            println!("  {}", line);
+        }
+    }
+
+    // Old points are left out of the charts, but only dropped from storage with `prune_storage`, so
+    // the full history can still be rendered later
+    let pruned = config
+        .retention
+        .is_enabled()
+        .then(|| plots.prune(&config.retention));
+    let (stored, rendered) = match &pruned {
+        Some(pruned) if config.retention.prune_storage => (pruned, pruned),
+        Some(pruned) => (&plots, pruned),
+        None => (&plots, &plots),
+    };
+    // Selected after pruning, so `max_age` still counts back from the newest point of any group
+    let rendered_selection = config
+        .groups
+        .is_enabled()
+        .then(|| rendered.select(&config.groups));
+    let rendered = rendered_selection.as_ref().unwrap_or(rendered);
+
+    // Write to disk
+    let start = Instant::now();
+    write_plots_to_file(&output_dir, stored)
+        .context("Failed to write `Plots` to `plot-data.json`")?;
+    let charts = generate_plots(rendered, &config.chart, &mut FileSink::new(&output_dir))
+        .map_err(|e| anyhow!("Failed to render the charts: {e}"))?;
+    debug!("wrote plot data and charts in {:?}", start.elapsed());
+
+    // Sent once the charts are saved, so the links work, and only reported if it fails
+    #[cfg(feature = "http")]
+    if let Some(webhook) = &slack_webhook {
+        let sha = &config.chart.commit_sha;
+        if let Err(e) = slack::notify(webhook, &regressions, &charts, plot_url.as_deref(), sha) {
+            error!("failed to notify Slack: {e:#}");
+        }
+    }
+
+    // Fail only once the data and charts are saved, so a gating CI job can still publish them
+    Ok(RunSummary {
+        groups_rendered: charts.into_keys().collect(),
+        points_added: changed,
+        regressions: regressions.iter().map(ToString::to_string).collect(),
+        failed: config.regression.fail && !regressions.is_empty(),
+    })
+}
@@ -0,0 +1,198 @@
+use plotters::prelude::*;
+use plotters_backend::{
+    BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingErrorKind,
+};
+
+// Backend adapter that multiplies every coordinate, stroke width and font size by an integer
+// factor before passing it to the wrapped backend. Charts are drawn against the logical (unscaled)
+// size, so their hard-coded pixel sizes keep the same proportions at any output resolution
+pub struct ScaledBackend<DB> {
+    inner: DB,
+    scale: u32,
+}
+
+impl<DB: DrawingBackend> ScaledBackend<DB> {
+    pub fn new(inner: DB, scale: u32) -> Self {
+        Self {
+            inner,
+            scale: scale.max(1),
+        }
+    }
+
+    fn coord(&self, (x, y): BackendCoord) -> BackendCoord {
+        (x * self.scale as i32, y * self.scale as i32)
+    }
+
+    fn style<S: BackendStyle>(&self, style: &S) -> ScaledStyle {
+        ScaledStyle {
+            color: style.color(),
+            stroke_width: style.stroke_width() * self.scale,
+        }
+    }
+
+    fn text_style<'a, TStyle: BackendTextStyle>(&self, style: &'a TStyle) -> TextStyle<'a> {
+        TextStyle {
+            font: FontDesc::new(
+                style.family(),
+                style.size() * self.scale as f64,
+                style.style(),
+            )
+            .transform(style.transform()),
+            color: style.color(),
+            pos: style.anchor(),
+        }
+    }
+}
+
+struct ScaledStyle {
+    color: BackendColor,
+    stroke_width: u32,
+}
+
+impl BackendStyle for ScaledStyle {
+    fn color(&self) -> BackendColor {
+        self.color
+    }
+
+    fn stroke_width(&self) -> u32 {
+        self.stroke_width
+    }
+}
+
+impl<DB: DrawingBackend> DrawingBackend for ScaledBackend<DB> {
+    type ErrorType = DB::ErrorType;
+
+    fn get_size(&self) -> (u32, u32) {
+        let (w, h) = self.inner.get_size();
+        (w / self.scale, h / self.scale)
+    }
+
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.inner.ensure_prepared()
+    }
+
+    fn present(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.inner.present()
+    }
+
+    // A logical pixel covers a `scale` x `scale` square of the wrapped backend
+    fn draw_pixel(
+        &mut self,
+        point: BackendCoord,
+        color: BackendColor,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let (x, y) = self.coord(point);
+        let end = (x + self.scale as i32 - 1, y + self.scale as i32 - 1);
+        let style = ScaledStyle {
+            color,
+            stroke_width: 1,
+        };
+        self.inner.draw_rect((x, y), end, &style, true)
+    }
+
+    fn draw_line<S: BackendStyle>(
+        &mut self,
+        from: BackendCoord,
+        to: BackendCoord,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        // Thick lines are drawn as polygons by the wrapped backend, which is how `draw_path` handles
+        // them too, so segments of a scaled path keep a consistent width
+        let style = self.style(style);
+        self.inner
+            .draw_path([self.coord(from), self.coord(to)], &style)
+    }
+
+    fn draw_rect<S: BackendStyle>(
+        &mut self,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let style = self.style(style);
+        self.inner.draw_rect(
+            self.coord(upper_left),
+            self.coord(bottom_right),
+            &style,
+            fill,
+        )
+    }
+
+    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        path: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let style = self.style(style);
+        let path = path.into_iter().map(|c| self.coord(c)).collect::<Vec<_>>();
+        self.inner.draw_path(path, &style)
+    }
+
+    fn draw_circle<S: BackendStyle>(
+        &mut self,
+        center: BackendCoord,
+        radius: u32,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let style = self.style(style);
+        self.inner
+            .draw_circle(self.coord(center), radius * self.scale, &style, fill)
+    }
+
+    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        vert: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let style = self.style(style);
+        let vert = vert.into_iter().map(|c| self.coord(c)).collect::<Vec<_>>();
+        self.inner.fill_polygon(vert, &style)
+    }
+
+    fn draw_text<TStyle: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &TStyle,
+        pos: BackendCoord,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let style = self.text_style(style);
+        self.inner.draw_text(text, &style, self.coord(pos))
+    }
+
+    // Reported in logical pixels, so layout done by plotters is independent of the scale
+    fn estimate_text_size<TStyle: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &TStyle,
+    ) -> Result<(u32, u32), DrawingErrorKind<Self::ErrorType>> {
+        let (w, h) = self
+            .inner
+            .estimate_text_size(text, &self.text_style(style))?;
+        Ok((w.div_ceil(self.scale), h.div_ceil(self.scale)))
+    }
+
+    // Upscales the RGB bitmap by repeating each pixel
+    fn blit_bitmap(
+        &mut self,
+        pos: BackendCoord,
+        (iw, ih): (u32, u32),
+        src: &[u8],
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let s = self.scale as usize;
+        let (iw, ih) = (iw as usize, ih as usize);
+        let mut scaled = Vec::with_capacity(src.len() * s * s);
+        for row in src.chunks(iw * 3).take(ih) {
+            let scaled_row = row
+                .chunks(3)
+                .flat_map(|px| std::iter::repeat(px).take(s).flatten().copied())
+                .collect::<Vec<_>>();
+            for _ in 0..s {
+                scaled.extend_from_slice(&scaled_row);
+            }
+        }
+        self.inner
+            .blit_bitmap(self.coord(pos), ((iw * s) as u32, (ih * s) as u32), &scaled)
+    }
+}
@@ -0,0 +1,44 @@
+use std::error::Error;
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+// Destination for rendered charts. `generate_plots` renders each chart into memory and hands the
+// encoded bytes to a sink, so storage (local disk, an S3 bucket, an HTTP endpoint) is pluggable
+pub trait ChartSink {
+    // Stores the encoded chart under `file_name`, e.g. `Fibonacci-num=10.png`, which may include
+    // `/`-separated subdirectories, e.g. `crypto/Poseidon-arity=4.png`
+    fn write_chart(&mut self, file_name: &str, bytes: &[u8]) -> Result<(), Box<dyn Error>>;
+}
+
+// Writes each chart to a file in the given directory
+pub struct FileSink {
+    dir: Utf8PathBuf,
+}
+
+impl FileSink {
+    pub fn new<P: AsRef<Utf8Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_owned(),
+        }
+    }
+}
+
+impl Default for FileSink {
+    fn default() -> Self {
+        Self::new(".")
+    }
+}
+
+impl ChartSink for FileSink {
+    fn write_chart(&mut self, file_name: &str, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+        let path = self.dir.join(file_name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Unable to create {}: {}", parent, e))?;
+        }
+        std::fs::write(&path, bytes)
+            .map_err(|e| format!("Unable to write result to {}: {}", path, e))?;
+        println!("Result has been saved to {}", path);
+        Ok(())
+    }
+}
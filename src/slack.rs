@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use anyhow::Context;
+use reqwest::{blocking::Client, Url};
+use serde_json::json;
+
+use crate::analysis::Regression;
+use crate::locale;
+
+// Posts the regressions to a Slack incoming webhook, one line per regression with its group, params
+// and change. Groups link to their chart under `plot_url` when it's given, e.g. where CI publishes
+// the output directory. Does nothing if there are no regressions
+pub fn notify(
+    webhook: &str,
+    regressions: &[Regression],
+    charts: &BTreeMap<String, String>,
+    plot_url: Option<&str>,
+    commit_sha: &str,
+) -> anyhow::Result<()> {
+    if regressions.is_empty() {
+        return Ok(());
+    }
+    let text = message(regressions, charts, plot_url, commit_sha);
+    Client::new()
+        .post(webhook)
+        .json(&json!({ "text": text }))
+        .send()?
+        .error_for_status()
+        .context("Slack rejected the message")?;
+    Ok(())
+}
+
+// Slack `mrkdwn` text listing each regression, e.g.
+// `• <https://example.com/Fibonacci-num=10.png|Fibonacci-num=10> `rc=100`: +10.0%`
+fn message(
+    regressions: &[Regression],
+    charts: &BTreeMap<String, String>,
+    plot_url: Option<&str>,
+    commit_sha: &str,
+) -> String {
+    let mut text = format!(
+        ":warning: {} benchmark regression{}",
+        regressions.len(),
+        if regressions.len() == 1 { "" } else { "s" }
+    );
+    if !commit_sha.is_empty() {
+        write!(text, " at `{}`", commit_sha).unwrap();
+    }
+    for regression in regressions {
+        let group = escape(regression.group);
+        let url = plot_url
+            .zip(charts.get(regression.group))
+            .and_then(|(base, file_name)| {
+                // Joining percent-encodes the name, e.g. the spaces in an alias
+                Url::parse(&format!("{}/", base.trim_end_matches('/')))
+                    .and_then(|base| base.join(file_name))
+                    .ok()
+            });
+        let group = match url {
+            Some(url) => format!("<{}|{}>", url, group),
+            None => group,
+        };
+        let change = locale::current().number(&format!("{:+.1}", regression.percent_change()));
+        write!(
+            text,
+            "\n• {} `{}`: {}%",
+            group,
+            escape(regression.params),
+            change
+        )
+        .unwrap();
+    }
+    text
+}
+
+// Escapes the characters Slack treats as control characters in `mrkdwn`
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,90 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde_json::{json, Value};
+
+use crate::config::RangeConfig;
+use crate::plot::Plot;
+
+const SCHEMA: &str = "https://vega.github.io/schema/vega-lite/v5.json";
+
+// Vega-Lite line chart of every params line in `plot`, with the points embedded as inline data.
+// Unlike the PNG charts it's drawn by the viewer, so it can be zoomed and panned, and each point
+// shows its commit and confidence interval on hover
+pub fn vega_spec(
+    name: &str,
+    plot: &Plot,
+    range: &RangeConfig,
+    (width, height): (u32, u32),
+) -> Value {
+    let values = plot
+        .lines
+        .iter()
+        .flat_map(|(params, points)| {
+            points.iter().map(move |p| {
+                json!({
+                    "params": params,
+                    "date": p.x.to_rfc3339(),
+                    "value": p.y,
+                    "lower": p.interval.map(|i| i.lower),
+                    "upper": p.interval.map(|i| i.upper),
+                    "sha": p.sha,
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Matches `draw_chart`, where a fixed X max covers the whole day
+    let midnight = |date: NaiveDate| DateTime::<Utc>::from_utc(date.and_hms(0, 0, 0), Utc);
+    let x_domain = [
+        range.x.min.map(|min| midnight(min).to_rfc3339()),
+        range
+            .x
+            .max
+            .map(|max| (midnight(max) + Duration::days(1)).to_rfc3339()),
+    ];
+
+    json!({
+        "$schema": SCHEMA,
+        "title": name,
+        "width": width,
+        "height": height,
+        "data": { "values": values },
+        "mark": { "type": "line", "point": true },
+        "params": [{ "name": "zoom", "select": "interval", "bind": "scales" }],
+        "encoding": {
+            "x": {
+                "field": "date",
+                "type": "temporal",
+                "title": "Commit Date",
+                "scale": domain(x_domain),
+            },
+            "y": {
+                "field": "value",
+                "type": "quantitative",
+                "title": plot.value_desc(),
+                "scale": domain([range.y.min, range.y.max]),
+            },
+            "color": { "field": "params", "type": "nominal", "title": "Params" },
+            "tooltip": [
+                { "field": "params", "type": "nominal" },
+                { "field": "date", "type": "temporal" },
+                { "field": "sha", "type": "nominal" },
+                { "field": "value", "type": "quantitative" },
+                { "field": "lower", "type": "quantitative" },
+                { "field": "upper", "type": "quantitative" },
+            ],
+        },
+    })
+}
+
+// Scale with fixed axis bounds. `domainMin` and `domainMax` each fix one end, so unset bounds are
+// still computed from the data
+fn domain<T: serde::Serialize>([min, max]: [Option<T>; 2]) -> Value {
+    let mut scale = json!({});
+    if let Some(min) = min {
+        scale["domainMin"] = json!(min);
+    }
+    if let Some(max) = max {
+        scale["domainMax"] = json!(max);
+    }
+    scale
+}
@@ -0,0 +1,147 @@
+mod support;
+
+use std::{fs, path::Path, process::Output};
+
+use serde_json::Value;
+use support::{read_plot_data, run_plotter};
+
+// Runs the plotter on a copy of `tests/fixtures/<name>`, returning its output and plot data
+fn plot_fixture(name: &str, args: &[&str]) -> (tempfile::TempDir, Output, Value) {
+    let dir = tempfile::tempdir().unwrap();
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    fs::copy(fixture, dir.path().join(name)).unwrap();
+    let output = run_plotter(dir.path(), args, None);
+    assert!(
+        output.status.success(),
+        "benchmark-plotter failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let plots = read_plot_data(dir.path());
+    (dir, output, plots)
+}
+
+// `(params, values)` of each line of `group`
+fn lines(plots: &Value, group: &str) -> Vec<(String, Vec<f64>)> {
+    plots[group]["lines"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(params, points)| {
+            let values = points
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["y"].as_f64().unwrap())
+                .collect();
+            (params.clone(), values)
+        })
+        .collect()
+}
+
+fn groups(plots: &Value) -> Vec<&str> {
+    plots
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect()
+}
+
+// Width and height from a PNG's `IHDR` chunk
+fn png_size(path: &Path) -> (u32, u32) {
+    let png = fs::read(path).unwrap();
+    assert_eq!(&png[1..4], b"PNG");
+    let dimension = |at: usize| u32::from_be_bytes(png[at..at + 4].try_into().unwrap());
+    (dimension(16), dimension(20))
+}
+
+// 2024-01-01 and 2024-01-02 at 10:00 -05:00, when the fixtures' commits are dated
+const DAY_1: i64 = 1704121200;
+const DAY_2: i64 = 1704207600;
+
+#[test]
+fn happy_path() {
+    let (dir, output, plots) = plot_fixture("happy.json", &[]);
+    assert!(
+        output.stderr.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(groups(&plots), ["Prove-num=10", "Verify-num=10"]);
+    assert_eq!(
+        lines(&plots, "Prove-num=10"),
+        [
+            ("rc=100".to_owned(), vec![2000000.0, 2100000.0]),
+            ("rc=200".to_owned(), vec![1500000.0, 1400000.0]),
+        ]
+    );
+    assert_eq!(
+        lines(&plots, "Verify-num=10"),
+        [("rc=100".to_owned(), vec![300000.0, 320000.0])]
+    );
+
+    let prove = &plots["Prove-num=10"];
+    assert_eq!(prove["x_axis"]["min"], DAY_1);
+    assert_eq!(prove["x_axis"]["max"], DAY_2);
+    assert_eq!(prove["y_axis"]["min"], 1400000.0);
+    assert_eq!(prove["y_axis"]["max"], 2100000.0);
+    let point = &prove["lines"]["rc=100"][0];
+    assert_eq!(point["sha"], "abc1234");
+    assert_eq!(point["unit"], "ns");
+    assert_eq!(point["interval"]["lower"], 1950000.0);
+    assert_eq!(point["interval"]["upper"], 2050000.0);
+
+    for group in groups(&plots) {
+        let chart = dir.path().join(format!("{}.png", group));
+        assert_eq!(png_size(&chart), (1024, 768));
+    }
+}
+
+#[test]
+fn malformed_entries_are_skipped() {
+    let (_dir, output, plots) = plot_fixture("malformed.json", &[]);
+    // The record with a numeric ID is stepped over, while the truncated one ends the file
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("skipped 2 malformed entries"), "{stderr}");
+    assert_eq!(
+        lines(&plots, "Prove-num=10"),
+        [("rc=100".to_owned(), vec![2000000.0, 2100000.0])]
+    );
+}
+
+#[test]
+fn bench_ids_with_two_and_four_segments() {
+    let (_dir, _output, plots) = plot_fixture("bench-ids.json", &[]);
+    assert_eq!(groups(&plots), ["Long", "Short"]);
+    // Params are empty without a third segment, and keep any after it
+    assert_eq!(lines(&plots, "Short"), [(String::new(), vec![1000.0])]);
+    assert_eq!(
+        lines(&plots, "Long"),
+        [("rc=100/extra=foo".to_owned(), vec![2000.0])]
+    );
+}
+
+#[test]
+fn nan_times_are_skipped() {
+    let (_dir, output, plots) = plot_fixture("nan.json", &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("whose value is NaN"), "{stderr}");
+    assert_eq!(
+        lines(&plots, "Prove-num=10"),
+        [("rc=100".to_owned(), vec![2100000.0])]
+    );
+    assert_eq!(plots["Prove-num=10"]["x_axis"]["min"], DAY_2);
+}
+
+#[test]
+fn single_point_group() {
+    let (dir, _output, plots) = plot_fixture("single-point.json", &["--scale", "2"]);
+    let single = &plots["Single-num=10"];
+    assert_eq!(single["x_axis"]["min"], single["x_axis"]["max"]);
+    assert_eq!(single["y_axis"]["min"], 5000.0);
+    assert_eq!(single["y_axis"]["max"], 5000.0);
+    let chart = dir.path().join("Single-num=10.png");
+    assert_eq!(png_size(&chart), (2048, 1536));
+}
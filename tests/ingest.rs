@@ -0,0 +1,715 @@
+mod support;
+
+use std::{
+    fs,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use flate2::{write::GzEncoder, Compression};
+use serde_json::{json, Value};
+use support::{
+    bench_record, line_values, plot_data, read_plot_data, run_plotter, synthetic_history,
+};
+
+#[test]
+fn estimate_accepts_integer_float_and_string() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "42"),
+        bench_record("bcd2345", "2024-01-02", "42.0"),
+        bench_record("cde3456", "2024-01-03", r#""42""#),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let plots = plot_data(dir.path(), &[], None);
+    assert_eq!(line_values(&plots), vec![42.0, 42.0, 42.0]);
+}
+
+#[test]
+fn conflicting_values_follow_policy() {
+    for (policy, stored, new, expected) in [
+        ("keep-existing", "10", "5", Some(10.0)),
+        ("overwrite", "10", "20", Some(20.0)),
+        ("keep-min", "10", "20", Some(10.0)),
+        ("keep-min", "10", "5", Some(5.0)),
+        ("error", "10", "5", None),
+    ] {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("old.json"),
+            bench_record("abc1234", "2024-01-01", stored),
+        )
+        .unwrap();
+        plot_data(dir.path(), &[], None);
+
+        fs::write(
+            dir.path().join("new.json"),
+            bench_record("abc1234", "2024-01-01", new),
+        )
+        .unwrap();
+        let args = ["--on-conflict", policy];
+        match expected {
+            Some(value) => {
+                let plots = plot_data(dir.path(), &args, Some("new"));
+                assert_eq!(line_values(&plots), vec![value], "policy `{policy}`");
+            }
+            None => {
+                let output = run_plotter(dir.path(), &args, Some("new"));
+                assert!(!output.status.success(), "policy `{policy}`");
+            }
+        }
+    }
+}
+
+#[test]
+fn synthetic_history_is_fully_ingested() {
+    let dir = tempfile::tempdir().unwrap();
+    let json = synthetic_history(2, 3, 5, 7);
+    assert_eq!(
+        json,
+        synthetic_history(2, 3, 5, 7),
+        "fixture isn't deterministic"
+    );
+    fs::write(dir.path().join("synthetic.json"), json).unwrap();
+
+    let plots = plot_data(dir.path(), &["--no-fail-on-regression"], None);
+    let plots = plots.as_object().unwrap();
+    assert_eq!(plots.len(), 2);
+    for plot in plots.values() {
+        let lines = plot["lines"].as_object().unwrap();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.values().all(|l| l.as_array().unwrap().len() == 5));
+    }
+}
+
+#[test]
+fn unchanged_data_is_not_rewritten_unless_forced() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("fib.json"),
+        bench_record("abc1234", "2024-01-01", "10"),
+    )
+    .unwrap();
+    plot_data(dir.path(), &[], None);
+
+    fs::remove_file(dir.path().join("Fibonacci-num=10.png")).unwrap();
+    let output = run_plotter(dir.path(), &[], Some("fib"));
+    assert!(output.stdout.is_empty() && output.stderr.is_empty());
+    let output = run_plotter(dir.path(), &["-v"], Some("fib"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no changes"));
+    assert!(!dir.path().join("Fibonacci-num=10.png").exists());
+
+    run_plotter(dir.path(), &["--force"], Some("fib"));
+    assert!(dir.path().join("Fibonacci-num=10.png").exists());
+}
+
+#[test]
+fn unreadable_bench_file_is_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("old.json"),
+        bench_record("abc1234", "2024-01-01", "10"),
+    )
+    .unwrap();
+    plot_data(dir.path(), &["--no-fail-on-regression"], None);
+
+    fs::write(
+        dir.path().join("new.json"),
+        bench_record("bcd2345", "2024-01-02", "20"),
+    )
+    .unwrap();
+    let output = run_plotter(
+        dir.path(),
+        &["--no-fail-on-regression"],
+        Some("missing,new"),
+    );
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing.json"));
+    let plots = plot_data(
+        dir.path(),
+        &["--force", "--no-fail-on-regression"],
+        Some("new"),
+    );
+    assert_eq!(line_values(&plots), vec![10.0, 20.0]);
+}
+
+#[test]
+fn malformed_entries_are_counted() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "10"),
+        r#"{"id":"not-a-criterion-id","typical":{"estimate":1}}"#.to_owned(),
+        bench_record("bcd2345", "2024-01-02", "20"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let output = run_plotter(dir.path(), &["--no-fail-on-regression"], None);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("skipped 1 malformed entries"));
+    let plots = plot_data(
+        dir.path(),
+        &["--force", "--no-fail-on-regression"],
+        Some("fib"),
+    );
+    assert_eq!(line_values(&plots), vec![10.0, 20.0]);
+}
+
+#[test]
+fn nan_time_is_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "10"),
+        bench_record("bcd2345", "2024-01-02", r#""NaN""#),
+        bench_record("cde3456", "2024-01-03", "30"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let output = run_plotter(dir.path(), &["--no-fail-on-regression"], None);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("whose value is NaN"));
+    let plots = plot_data(
+        dir.path(),
+        &["--force", "--no-fail-on-regression"],
+        Some("fib"),
+    );
+    assert_eq!(line_values(&plots), vec![10.0, 30.0]);
+}
+
+#[test]
+fn bench_names_with_any_sha_length() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record(
+            "0123456789abcdef0123456789abcdef01234567",
+            "2024-01-01",
+            "10",
+        ),
+        bench_record("abc", "2024-01-02", "20"),
+        r#"{"id":"Fibonacci-num=10/2024-01-03T00_00_00+00_00/rc=100","typical":{"estimate":30}}"#
+            .to_owned(),
+        // Anything before the date is taken as the SHA, and shortened by characters, not bytes
+        bench_record("abcdefé0", "2024-01-04", "40"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let plots = plot_data(dir.path(), &["--no-fail-on-regression"], None);
+    assert_eq!(line_values(&plots), vec![10.0, 20.0, 30.0, 40.0]);
+    let shas = plots["Fibonacci-num=10"]["lines"]["rc=100"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["sha"].as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        shas,
+        vec![Some("0123456"), Some("abc"), None, Some("abcdefé")]
+    );
+
+    fs::write(
+        dir.path().join("bad.json"),
+        r#"{"id":"Fibonacci-num=10/abc1234-yesterday/rc=100","typical":{"estimate":40}}"#,
+    )
+    .unwrap();
+    let output = run_plotter(dir.path(), &["--no-fail-on-regression"], Some("bad"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("`abc1234-yesterday`"));
+}
+
+#[test]
+fn bench_ids_with_two_to_four_segments() {
+    let dir = tempfile::tempdir().unwrap();
+    let record = |id: &str| format!(r#"{{"id":"{id}","typical":{{"estimate":1}}}}"#);
+    let records = [
+        record("Fib/abc1234-2024-01-01T00_00_00+00_00"),
+        record("Fib/abc1234-2024-01-01T00_00_00+00_00/rc=100"),
+        record("Fib/abc1234-2024-01-01T00_00_00+00_00/rc=100/extra=foo"),
+        record("Fib"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let plots = plot_data(dir.path(), &[], None);
+    let mut params = plots["Fib"]["lines"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>();
+    params.sort();
+    assert_eq!(params, vec!["", "rc=100", "rc=100/extra=foo"]);
+}
+
+#[test]
+fn memory_is_plotted_separately() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("fib.json"),
+        r#"{"id":"Fib/abc1234-2024-01-01T00_00_00+00_00/rc=100","typical":{"estimate":10},"memory":{"estimate":2048,"unit":"bytes"}}"#,
+    )
+    .unwrap();
+
+    let plots = plot_data(dir.path(), &[], None);
+    assert_eq!(plots["Fib"]["kind"], "time");
+    assert_eq!(plots["Fib"]["lines"]["rc=100"][0]["y"], 10.0);
+    assert_eq!(plots["Fib.memory"]["kind"], "memory");
+    assert_eq!(plots["Fib.memory"]["lines"]["rc=100"][0]["y"], 2048.0);
+    assert!(dir.path().join("Fib.memory.png").exists());
+}
+
+#[test]
+fn same_results_added_twice_are_deduplicated() {
+    let dir = tempfile::tempdir().unwrap();
+    let record = bench_record("abc1234", "2024-01-01", "10");
+    fs::write(dir.path().join("fib.json"), &record).unwrap();
+    fs::write(dir.path().join("fib-copy.json"), &record).unwrap();
+
+    // Both files are read in the same run when there's no plot data yet
+    let plots = plot_data(dir.path(), &[], None);
+    assert_eq!(line_values(&plots), vec![10.0]);
+
+    let plots = plot_data(dir.path(), &["--force"], Some("fib,fib-copy"));
+    assert_eq!(line_values(&plots), vec![10.0]);
+}
+
+#[test]
+fn results_are_read_from_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    // Ignored in favor of stdin
+    fs::write(
+        dir.path().join("fib.json"),
+        bench_record("abc1234", "2024-01-01", "10"),
+    )
+    .unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_benchmark-plotter"))
+        .arg("--stdin")
+        .current_dir(dir.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(bench_record("bcd2345", "2024-01-02", "20").as_bytes())
+        .unwrap();
+    assert!(child.wait().unwrap().success());
+
+    assert_eq!(line_values(&read_plot_data(dir.path())), vec![20.0]);
+}
+
+#[test]
+fn glob_selects_bench_files_in_subdirectories() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("runs/fib")).unwrap();
+    fs::write(
+        dir.path().join("runs/fib/new.json"),
+        bench_record("abc1234", "2024-01-01", "10"),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("other.json"),
+        bench_record("bcd2345", "2024-01-02", "20"),
+    )
+    .unwrap();
+
+    let plots = plot_data(dir.path(), &["--glob", "runs/**/*.json"], None);
+    assert_eq!(line_values(&plots), vec![10.0]);
+
+    let output = run_plotter(dir.path(), &["--glob", "runs/[*.json"], None);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid value"));
+}
+
+#[test]
+fn old_points_are_pruned_from_storage_only_when_asked() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "10"),
+        bench_record("bcd2345", "2024-01-05", "20"),
+        bench_record("cde3456", "2024-01-06", "30"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let plots = plot_data(
+        dir.path(),
+        &["--max-points", "2", "--no-fail-on-regression"],
+        None,
+    );
+    assert_eq!(line_values(&plots), vec![10.0, 20.0, 30.0]);
+
+    let plots = plot_data(
+        dir.path(),
+        &[
+            "--max-age",
+            "2d",
+            "--prune-storage",
+            "--force",
+            "--no-fail-on-regression",
+        ],
+        Some("fib"),
+    );
+    assert_eq!(line_values(&plots), vec![20.0, 30.0]);
+    assert_eq!(
+        plots["Fibonacci-num=10"]["x_axis"]["min"],
+        json!(1704412800)
+    );
+
+    let output = run_plotter(
+        dir.path(),
+        &["--max-age", "90", "--no-fail-on-regression"],
+        None,
+    );
+    assert!(!output.status.success());
+}
+
+#[test]
+fn shards_are_merged() {
+    let shards = [
+        [
+            ("abc1234", "2024-01-01", "10"),
+            ("bcd2345", "2024-01-02", "30"),
+        ],
+        [
+            ("bcd2345", "2024-01-02", "20"),
+            ("cde3456", "2024-01-03", "40"),
+        ],
+    ];
+    let dir = tempfile::tempdir().unwrap();
+    for (i, shard) in shards.iter().enumerate() {
+        let shard_dir = dir.path().join(format!("shard-{i}"));
+        fs::create_dir(&shard_dir).unwrap();
+        let records = shard
+            .iter()
+            .map(|(sha, date, estimate)| bench_record(sha, date, estimate))
+            .collect::<Vec<_>>();
+        fs::write(shard_dir.join("fib.json"), records.join("\n")).unwrap();
+        plot_data(&shard_dir, &["--no-fail-on-regression"], None);
+    }
+
+    let args = [
+        "--no-fail-on-regression",
+        "--merge",
+        "shard-0/plot-data.json",
+        "shard-1/plot-data.json",
+    ];
+    let plots = plot_data(dir.path(), &args, None);
+    assert_eq!(line_values(&plots), vec![10.0, 20.0, 40.0]);
+
+    let output = run_plotter(
+        dir.path(),
+        &[&args[..], &["--on-conflict", "error"]].concat(),
+        None,
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Conflicting values"));
+
+    // A shard of throughput isn't mixed into the plot of times
+    let shard_dir = dir.path().join("shard-2");
+    fs::create_dir(&shard_dir).unwrap();
+    let record = bench_record("def4567", "2024-01-04", "50").replace(
+        r#","typical""#,
+        r#","throughput":[{"per_iteration":1000,"unit":"bytes"}],"typical""#,
+    );
+    fs::write(shard_dir.join("fib.json"), record).unwrap();
+    plot_data(
+        &shard_dir,
+        &["--metric", "throughput", "--no-fail-on-regression"],
+        None,
+    );
+    let output = run_plotter(
+        dir.path(),
+        &[&args[..], &["shard-2/plot-data.json"]].concat(),
+        None,
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("skipping Fibonacci-num=10 of the merged plot data"),
+        "{stderr}"
+    );
+    assert_eq!(
+        line_values(&read_plot_data(dir.path())),
+        vec![10.0, 20.0, 40.0]
+    );
+}
+
+#[test]
+fn plot_data_is_written_in_sorted_order() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("bench.json"), synthetic_history(3, 3, 2, 5)).unwrap();
+    plot_data(dir.path(), &["--no-fail-on-regression"], None);
+
+    let json = fs::read_to_string(dir.path().join("plot-data.json")).unwrap();
+    let position = |key: &str| json.find(&format!("\"{key}\"")).unwrap();
+    for keys in [
+        [
+            "Synthetic-group=0",
+            "Synthetic-group=1",
+            "Synthetic-group=2",
+        ],
+        ["n=100", "n=200", "n=300"],
+    ] {
+        assert!(position(keys[0]) < position(keys[1]));
+        assert!(position(keys[1]) < position(keys[2]));
+    }
+}
+
+#[test]
+fn out_of_order_results_are_inserted_in_place() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("bcd2345", "2024-01-02", "200"),
+        bench_record("def4567", "2024-01-04", "400"),
+    ];
+    fs::write(dir.path().join("old.json"), records.join("\n")).unwrap();
+    plot_data(dir.path(), &["--no-fail-on-regression"], None);
+
+    // Later results land before, between and after the stored ones, and replace one of them
+    let records = [
+        bench_record("efa5678", "2024-01-05", "500"),
+        bench_record("abc1234", "2024-01-01", "100"),
+        bench_record("cde3456", "2024-01-03", "300"),
+        bench_record("bcd2345", "2024-01-02", "250"),
+    ];
+    fs::write(dir.path().join("new.json"), records.join("\n")).unwrap();
+    let plots = plot_data(
+        dir.path(),
+        &["--on-conflict", "overwrite", "--no-fail-on-regression"],
+        Some("new"),
+    );
+    assert_eq!(line_values(&plots), vec![100.0, 250.0, 300.0, 400.0, 500.0]);
+    let plot = &plots["Fibonacci-num=10"];
+    assert_eq!(plot["y_axis"]["min"], 100.0);
+    assert_eq!(plot["y_axis"]["max"], 500.0);
+}
+
+#[test]
+fn unversioned_plot_data_is_upgraded() {
+    let dir = tempfile::tempdir().unwrap();
+    // A bare map of the plots, without axes, unit or metric, and a field this version doesn't know
+    fs::write(
+        dir.path().join("plot-data.json"),
+        r#"{"Fibonacci-num=10":{"lines":{"rc=100":[{"x":"2024-01-01T00:00:00Z","y":10.0,"noise":1}]},"future":true}}"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("fib.json"),
+        bench_record("bcd2345", "2024-01-02", "20"),
+    )
+    .unwrap();
+    plot_data(dir.path(), &["--no-fail-on-regression"], Some("fib"));
+
+    let json: Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("plot-data.json")).unwrap())
+            .unwrap();
+    assert_eq!(json["version"], 1);
+    let plots = &json["plots"];
+    assert_eq!(line_values(plots), vec![10.0, 20.0]);
+    assert_eq!(plots["Fibonacci-num=10"]["y_axis"]["min"], 10.0);
+
+    // Data from a newer version isn't replaced by a fresh file
+    let newer = r#"{"version":2,"plots":{}}"#;
+    fs::write(dir.path().join("plot-data.json"), newer).unwrap();
+    let output = run_plotter(dir.path(), &["--no-fail-on-regression"], Some("fib"));
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("newer than the supported version 1"),
+        "{stderr}"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("plot-data.json")).unwrap(),
+        newer
+    );
+}
+
+#[test]
+fn gzipped_bench_files_are_read() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut gz = GzEncoder::new(
+        fs::File::create(dir.path().join("fib.json.gz")).unwrap(),
+        Compression::default(),
+    );
+    gz.write_all(bench_record("abc1234", "2024-01-01", "10").as_bytes())
+        .unwrap();
+    gz.finish().unwrap();
+    fs::write(
+        dir.path().join("new.json"),
+        bench_record("bcd2345", "2024-01-02", "20"),
+    )
+    .unwrap();
+
+    let plots = plot_data(dir.path(), &["--no-fail-on-regression"], None);
+    assert_eq!(line_values(&plots), vec![10.0, 20.0]);
+}
+
+#[test]
+fn config_file_is_overridden_by_flags() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("fib.json"),
+        bench_record("abc1234", "2024-01-01", "10"),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("plotter.toml"),
+        "output_dir = \"out\"\n[chart]\nformat = \"svg\"\n",
+    )
+    .unwrap();
+
+    let output = run_plotter(dir.path(), &["--config", "plotter.toml"], None);
+    assert!(output.status.success());
+    assert!(dir.path().join("out/Fibonacci-num=10.svg").exists());
+
+    let args = ["--config", "plotter.toml", "--format", "png", "--force"];
+    let output = run_plotter(dir.path(), &args, None);
+    assert!(output.status.success());
+    assert!(dir.path().join("out/Fibonacci-num=10.png").exists());
+
+    let output = run_plotter(dir.path(), &["--config", "missing.toml"], None);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn sha_flag_selects_the_commits_bench_files() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("fib-abc1234.json"),
+        bench_record("abc1234", "2024-01-01", "10"),
+    )
+    .unwrap();
+    plot_data(dir.path(), &["--no-fail-on-regression"], None);
+
+    for (sha, date, estimate) in [
+        ("bcd2345", "2024-01-02", "20"),
+        ("cde3456", "2024-01-03", "30"),
+    ] {
+        fs::write(
+            dir.path().join(format!("fib-{sha}.json")),
+            bench_record(sha, date, estimate),
+        )
+        .unwrap();
+    }
+    let plots = plot_data(
+        dir.path(),
+        &["--sha", "bcd2345abcdef", "--no-fail-on-regression"],
+        None,
+    );
+    assert_eq!(line_values(&plots), vec![10.0, 20.0]);
+
+    let output = run_plotter(
+        dir.path(),
+        &["--sha", "not-a-sha", "--no-fail-on-regression"],
+        None,
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid value"));
+}
+
+#[test]
+fn entries_with_bad_timestamps_are_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "10"),
+        r#"{"id":"Fibonacci-num=10/bcd2345-2024-13-45T25_00_00/rc=100","typical":{"estimate":20}}"#
+            .to_owned(),
+        bench_record("cde3456", "2024-01-03", "30"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let output = run_plotter(dir.path(), &["--no-fail-on-regression"], None);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Fibonacci-num=10 `rc=100`"));
+    assert!(stderr.contains("`bcd2345-2024-13-45T25:00:00`"));
+    let plots = plot_data(dir.path(), &["--force", "--no-fail-on-regression"], None);
+    assert_eq!(line_values(&plots), vec![10.0, 30.0]);
+}
+
+#[test]
+fn dry_run_summarizes_without_writing() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "100"),
+        bench_record("bcd2345", "2024-01-02", "150"),
+        "{\"id\":\"no-name\"}".to_owned(),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let output = run_plotter(dir.path(), &["--dry-run"], None);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Bench files read:\n  ./fib.json\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("./fib.json: Expected a bench ID"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("Fibonacci-num=10: 1 lines, 2 points"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("Fibonacci-num=10 `rc=100`: 100.00 -> 150.00"),
+        "{stdout}"
+    );
+    let written = fs::read_dir(dir.path()).unwrap().count();
+    assert_eq!(written, 1, "only the bench file should exist");
+}
+
+#[test]
+fn validate_reports_rejects_and_gates_on_the_rate() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "100"),
+        bench_record("bcd2345", "2024-01-02", "100"),
+        bench_record("cde3456", "2024-01-03", "100"),
+        // A producer that renamed its estimate
+        bench_record("def4567", "2024-01-04", "100").replace("typical", "mean"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let output = run_plotter(dir.path(), &["--validate"], None);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "./fib.json: 3 matched, 1 rejected (25.0%)\n  1 without a `typical` estimate"
+        ),
+        "{stdout}"
+    );
+    assert!(!dir.path().join("plot-data.json").exists());
+
+    let output = run_plotter(
+        dir.path(),
+        &["--validate", "--max-reject-rate", "0.25"],
+        None,
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn bench_id_format_is_configurable() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        r#"{"id":"rc=100::Fibonacci-num=10::abc1234-2024-01-01T00:00:00+00:00","typical":{"estimate":10}}"#,
+        r#"{"id":"Fibonacci-num=10/abc1234-2024-01-02T00_00_00+00_00/rc=100","typical":{"estimate":20}}"#,
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+    fs::write(
+        dir.path().join("plotter.toml"),
+        "[ingest.bench_id]\nseparator = \"::\"\nsegments = [\"params\", \"group\", \"bench\"]\nrestore_colons = false\n",
+    )
+    .unwrap();
+
+    // The Criterion-style ID doesn't match the format, so only the first result is plotted
+    let plots = plot_data(dir.path(), &["--config", "plotter.toml"], None);
+    assert_eq!(line_values(&plots), [10.0]);
+
+    fs::write(
+        dir.path().join("plotter.toml"),
+        "[ingest.bench_id]\nsegments = [\"group\", \"params\"]\n",
+    )
+    .unwrap();
+    let output = run_plotter(dir.path(), &["--config", "plotter.toml"], None);
+    assert!(!output.status.success());
+}
@@ -0,0 +1,40 @@
+mod support;
+
+use std::fs;
+
+use benchmark_plotter::config::{Config, Metric};
+use benchmark_plotter::{read_json_from_reader, run, Extractor, Plots};
+use camino::Utf8PathBuf;
+use support::{bench_record, line_values, read_plot_data};
+
+#[test]
+fn pipeline_runs_as_a_library() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "100"),
+        bench_record("bcd2345", "2024-01-02", "150"),
+    ]
+    .join("\n");
+    fs::write(dir.path().join("fib.json"), &records).unwrap();
+
+    let (data, errors) = read_json_from_reader(
+        records.as_bytes(),
+        &Extractor::Time(Metric::Typical),
+        &Default::default(),
+    )
+    .unwrap();
+    assert!(errors.is_empty());
+    let mut config = Config::default();
+    assert_eq!(Plots::new().add_data(&data, &config.ingest).unwrap(), 2);
+
+    let path = Utf8PathBuf::from_path_buf(dir.path().to_owned()).unwrap();
+    config.output_dir = Some(path.to_string());
+    config.run.input_dir = path;
+    let summary = run(config).unwrap();
+    assert_eq!(summary.groups_rendered, ["Fibonacci-num=10"]);
+    assert_eq!(summary.points_added, 2);
+    assert_eq!(summary.regressions.len(), 1);
+    assert!(summary.failed);
+    assert!(dir.path().join("Fibonacci-num=10.png").exists());
+    assert_eq!(line_values(&read_plot_data(dir.path())), [100.0, 150.0]);
+}
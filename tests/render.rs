@@ -0,0 +1,533 @@
+mod support;
+
+use std::{collections::HashMap, fs};
+
+use support::{bench_record, plot_data, read_plot_data, run_plotter, synthetic_history};
+
+// Contents of every `<text>` element in an SVG chart, i.e. each label, tick and caption
+fn svg_texts(svg: &str) -> Vec<&str> {
+    svg.split("<text")
+        .skip(1)
+        .filter_map(|text| text.split_once('>')?.1.split_once("</text>"))
+        .map(|(content, _)| content.trim())
+        .collect()
+}
+
+#[test]
+fn legend_has_one_entry_per_line() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("bench.json"),
+        synthetic_history(1, 4, 3, 11),
+    )
+    .unwrap();
+
+    plot_data(dir.path(), &["--format", "svg"], None);
+    let svg = fs::read_to_string(dir.path().join("Synthetic-group=0.svg")).unwrap();
+    let entries = svg_texts(&svg)
+        .into_iter()
+        .filter(|text| text.starts_with("n="))
+        .collect::<Vec<_>>();
+    assert_eq!(entries, vec!["n=100", "n=200", "n=300", "n=400"]);
+}
+
+#[test]
+fn groups_with_zero_or_one_point() {
+    let dir = tempfile::tempdir().unwrap();
+    // An empty group as saved before the X range could be unset, with min after max
+    fs::write(
+        dir.path().join("plot-data.json"),
+        r#"{"Empty":{"x_axis":{"min":1704067200,"max":-8334632851200},"y_axis":{"min":1.7976931348623157e308,"max":-1.7976931348623157e308},"lines":{}}}"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("fib.json"),
+        bench_record("abc1234", "2024-01-01", "10"),
+    )
+    .unwrap();
+
+    let output = run_plotter(dir.path(), &["--combined", "all.png"], Some("fib"));
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("skipping Empty"));
+    assert!(!dir.path().join("Empty.png").exists());
+    assert!(dir.path().join("Fibonacci-num=10.png").exists());
+    assert!(dir.path().join("all.png").exists());
+
+    let plots = read_plot_data(dir.path());
+    let x_axis = &plots["Fibonacci-num=10"]["x_axis"];
+    assert_eq!(x_axis["min"], x_axis["max"]);
+}
+
+#[test]
+fn dark_theme_has_no_black_elements() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("bench.json"), synthetic_history(1, 2, 3, 7)).unwrap();
+
+    plot_data(
+        dir.path(),
+        &[
+            "--format",
+            "svg",
+            "--theme",
+            "dark",
+            "--geo-mean",
+            "complete",
+        ],
+        None,
+    );
+    let svg = fs::read_to_string(dir.path().join("Synthetic-group=0.svg")).unwrap();
+    assert!(svg.contains(r##"fill="#1E1E1E""##));
+    assert!(!svg.contains("#000000"));
+}
+
+// Fill color of each legend swatch, keyed by the label drawn after it
+fn legend_colors(svg: &str) -> HashMap<&str, &str> {
+    svg.split("<rect")
+        .skip(1)
+        .filter_map(|rect| {
+            let fill = rect.split_once("fill=\"")?.1.split_once('"')?.0;
+            Some((*svg_texts(rect).first()?, fill))
+        })
+        .collect()
+}
+
+#[test]
+fn line_colors_are_stable_and_configurable() {
+    let record = |params: &str| {
+        format!(
+            r#"{{"id":"Fib/abc1234-2024-01-01T00_00_00+00_00/{params}","typical":{{"estimate":10}}}}"#
+        )
+    };
+    let colors = |params: &[&str], args: &[&str]| {
+        let dir = tempfile::tempdir().unwrap();
+        let records = params.iter().map(|p| record(p)).collect::<Vec<_>>();
+        fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+        plot_data(dir.path(), &[&["--format", "svg"], args].concat(), None);
+        let svg = fs::read_to_string(dir.path().join("Fib.svg")).unwrap();
+        legend_colors(&svg)
+            .into_iter()
+            .map(|(label, fill)| (label.to_owned(), fill.to_owned()))
+            .collect::<HashMap<_, _>>()
+    };
+
+    // Adding a line that sorts first doesn't recolor the others
+    let before = colors(&["rc=200", "rc=300"], &[]);
+    let after = colors(&["rc=100", "rc=200", "rc=300"], &[]);
+    assert_eq!(before["rc=200"], after["rc=200"]);
+    assert_eq!(before["rc=300"], after["rc=300"]);
+    assert_ne!(after["rc=100"], after["rc=200"]);
+
+    let custom = colors(&["rc=100", "rc=200"], &["--colors", "rc=100:#ff0000"]);
+    assert_eq!(custom["rc=100"], "#FF0000");
+    assert_eq!(custom["rc=200"], after["rc=200"]);
+}
+
+#[test]
+fn index_axis_labels_commits_by_sha() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "10"),
+        bench_record("bcd2345", "2024-03-01", "20"),
+        bench_record("cde3456", "2024-03-02", "30"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    plot_data(
+        dir.path(),
+        &[
+            "--format",
+            "svg",
+            "--x-axis",
+            "index",
+            "--no-fail-on-regression",
+        ],
+        None,
+    );
+    let svg = fs::read_to_string(dir.path().join("Fibonacci-num=10.svg")).unwrap();
+    let texts = svg_texts(&svg);
+    let shas = texts
+        .iter()
+        .filter(|text| text.len() == 7 && text.chars().all(|c| c.is_ascii_hexdigit()))
+        .collect::<Vec<_>>();
+    assert_eq!(shas, [&"abc1234", &"bcd2345", &"cde3456"]);
+    assert!(texts.contains(&"Commit"));
+}
+
+#[test]
+fn highlighted_regressions_are_labeled() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "100"),
+        bench_record("bcd2345", "2024-01-02", "110"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let args = [
+        "--format",
+        "svg",
+        "--highlight-regressions",
+        "--highlight-labels",
+        "--no-fail-on-regression",
+    ];
+    plot_data(dir.path(), &args, None);
+    let svg = fs::read_to_string(dir.path().join("Fibonacci-num=10.svg")).unwrap();
+    let texts = svg_texts(&svg);
+    assert!(texts.contains(&"+10.0%"), "{texts:?}");
+    assert!(texts.contains(&"regression"));
+}
+
+#[test]
+fn bands_are_shaded_behind_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("bench.json"), synthetic_history(1, 2, 6, 3)).unwrap();
+
+    plot_data(dir.path(), &["--format", "svg"], None);
+    let svg = fs::read_to_string(dir.path().join("Synthetic-group=0.svg")).unwrap();
+    assert!(!svg.contains(r#"<polygon opacity="0.15""#));
+
+    plot_data(
+        dir.path(),
+        &["--format", "svg", "--show-band", "--force"],
+        None,
+    );
+    let svg = fs::read_to_string(dir.path().join("Synthetic-group=0.svg")).unwrap();
+    assert_eq!(svg.matches(r#"<polygon opacity="0.15""#).count(), 2);
+}
+
+#[test]
+fn dashboard_tiles_groups_in_a_grid() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("bench.json"),
+        synthetic_history(5, 2, 3, 11),
+    )
+    .unwrap();
+
+    plot_data(
+        dir.path(),
+        &[
+            "--format",
+            "svg",
+            "--dashboard",
+            "dashboard.svg",
+            "--no-fail-on-regression",
+        ],
+        None,
+    );
+    // 5 groups fit in 2 rows of 3 half-size charts
+    let svg = fs::read_to_string(dir.path().join("dashboard.svg")).unwrap();
+    assert!(svg.contains(r#"width="1536" height="768""#));
+    let texts = svg_texts(&svg);
+    for group in 0..5 {
+        let caption = format!("Synthetic-group={}", group);
+        assert!(texts.contains(&caption.as_str()));
+    }
+}
+
+#[test]
+fn dates_are_labeled_in_the_chosen_timezone() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("bench.json"), synthetic_history(1, 1, 3, 2)).unwrap();
+
+    plot_data(
+        dir.path(),
+        &["--format", "svg", "--timezone", "-05:00"],
+        None,
+    );
+    let svg = fs::read_to_string(dir.path().join("Synthetic-group=0.svg")).unwrap();
+    assert!(svg_texts(&svg).iter().any(|t| t.ends_with(" 19:00 -05:00")));
+
+    let output = run_plotter(dir.path(), &["--timezone", "EST"], None);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid value"));
+}
+
+#[test]
+fn flat_lines_get_a_nonempty_y_range() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = (1..=3)
+        .map(|day| bench_record(&format!("abc123{day}"), &format!("2024-01-0{day}"), "0"))
+        .collect::<Vec<_>>();
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    plot_data(dir.path(), &["--format", "svg"], None);
+    let svg = fs::read_to_string(dir.path().join("Fibonacci-num=10.svg")).unwrap();
+    let y_labels = svg_texts(&svg)
+        .into_iter()
+        .filter(|t| t.parse::<f64>().is_ok())
+        .count();
+    assert!(y_labels >= 2, "{:?}", svg_texts(&svg));
+}
+
+#[test]
+fn compare_charts_two_commits_head_to_head() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "100"),
+        bench_record("bcd2345", "2024-01-02", "500"),
+        bench_record("cde3456", "2024-01-03", "125"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let output = run_plotter(
+        dir.path(),
+        &["--format", "svg", "--compare", "abc1234", "cde3456"],
+        None,
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("| Benchmark | Params | abc1234 | cde3456 | Change |"));
+    assert!(stdout.contains("| Fibonacci-num=10 | rc=100 | 100.00 ns | 125.00 ns | ▲ +25.0% |"));
+
+    let svg = fs::read_to_string(dir.path().join("Fibonacci-num=10.compare.svg")).unwrap();
+    let texts = svg_texts(&svg);
+    assert!(texts.contains(&"+25.0%"), "{texts:?}");
+    assert!(texts.contains(&"rc=100"), "{texts:?}");
+    // Only the bars are written, not the history
+    assert!(!dir.path().join("plot-data.json").exists());
+    assert!(!dir.path().join("Fibonacci-num=10.svg").exists());
+
+    // SHAs too short to tell commits apart are rejected, as are ones matching two stored commits
+    let output = run_plotter(dir.path(), &["--compare", "abc", "cde3456"], None);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("at least 7 characters"), "{stderr}");
+    let records = [&records[..], &[bench_record("abc12", "2024-01-04", "90")]].concat();
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+    let output = run_plotter(dir.path(), &["--compare", "abc1234", "cde3456"], None);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("`abc1234` matches more than one commit: abc12, abc1234"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn highlighted_changes_show_commit_subjects() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path().join("repo");
+    fs::create_dir(&repo).unwrap();
+    let git = |args: &[&str]| {
+        let output = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(&repo)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap().trim().to_owned()
+    };
+    git(&["init", "-q"]);
+    git(&[
+        "commit",
+        "-q",
+        "--allow-empty",
+        "-m",
+        "Slow down fib\n\nDetails",
+    ]);
+    let sha = git(&["rev-parse", "--short=7", "HEAD"]);
+    // The first commit isn't in the repository, like one left out of a shallow clone
+    let records = [
+        bench_record("abc1234", "2024-01-01", "100"),
+        bench_record(&sha, "2024-01-02", "110"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let args = [
+        "--format",
+        "svg",
+        "--highlight-regressions",
+        "--html",
+        "report.html",
+        "--git-dir",
+        "repo",
+        "--no-fail-on-regression",
+    ];
+    plot_data(dir.path(), &args, None);
+    let svg = fs::read_to_string(dir.path().join("Fibonacci-num=10.svg")).unwrap();
+    assert!(
+        svg_texts(&svg).contains(&"Slow down fib"),
+        "{:?}",
+        svg_texts(&svg)
+    );
+    let html = fs::read_to_string(dir.path().join("report.html")).unwrap();
+    assert!(html.contains(r#""message":"Slow down fib""#));
+    assert!(html.contains(r#""message":null"#));
+}
+
+#[test]
+fn only_and_exclude_select_the_rendered_groups() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("bench.json"), synthetic_history(3, 1, 2, 9)).unwrap();
+
+    let args = [
+        "--format",
+        "svg",
+        "--only",
+        "Synthetic-*",
+        "--exclude",
+        "*=1",
+        "--export-csv",
+        "points.csv",
+    ];
+    let plots = plot_data(dir.path(), &args, None);
+    let charts = [
+        "Synthetic-group=0",
+        "Synthetic-group=1",
+        "Synthetic-group=2",
+    ]
+    .map(|group| dir.path().join(format!("{group}.svg")).exists());
+    assert_eq!(charts, [true, false, true]);
+    let csv = fs::read_to_string(dir.path().join("points.csv")).unwrap();
+    assert!(!csv.contains("Synthetic-group=1"));
+    // Every group is still stored
+    assert_eq!(plots.as_object().unwrap().len(), 3);
+}
+
+#[test]
+fn deltas_label_steps_beyond_the_minimum() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "100"),
+        bench_record("bcd2345", "2024-01-02", "150"),
+        bench_record("cde3456", "2024-01-03", "151"),
+        bench_record("def4567", "2024-01-04", "120"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let args = [
+        "--format",
+        "svg",
+        "--show-deltas",
+        "--delta-min-change",
+        "0.05",
+    ];
+    plot_data(dir.path(), &args, None);
+    let svg = fs::read_to_string(dir.path().join("Fibonacci-num=10.svg")).unwrap();
+    let texts = svg_texts(&svg);
+    assert!(texts.contains(&"+50.0%"), "{texts:?}");
+    assert!(texts.contains(&"-20.5%"), "{texts:?}");
+    // A 0.7% step is within the minimum
+    assert!(
+        !texts.iter().any(|text| text.starts_with("+0.")),
+        "{texts:?}"
+    );
+}
+
+#[test]
+fn baselines_are_drawn_in_the_plots_unit() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "100"),
+        bench_record("bcd2345", "2024-01-02", "150"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let args = [
+        "--format",
+        "svg",
+        "--baseline",
+        "Fibonacci-num=10/rc=100=0.12us",
+        "--baseline",
+        "Fibonacci-num=10=2MB",
+        "--no-fail-on-regression",
+    ];
+    let output = run_plotter(dir.path(), &args, None);
+    assert!(output.status.success());
+    let svg = fs::read_to_string(dir.path().join("Fibonacci-num=10.svg")).unwrap();
+    let texts = svg_texts(&svg);
+    assert!(texts.contains(&"baseline rc=100"), "{texts:?}");
+    // A memory baseline doesn't convert to nanoseconds
+    assert!(!texts.contains(&"baseline"), "{texts:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("skipping the baseline of Fibonacci-num=10"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn targets_are_reached_in_the_better_direction() {
+    let with_throughput = |sha, date, estimate| {
+        bench_record(sha, date, estimate).replace(
+            r#","typical""#,
+            r#","throughput":[{"per_iteration":1000,"unit":"bytes"}],"typical""#,
+        )
+    };
+    // Times fall below the target on the 3rd, while throughput (1000 bytes in 1000 ns is 1e9 B/s)
+    // only rises to its target on the 2nd
+    for (args, target, records, reached) in [
+        (
+            vec!["--format", "svg"],
+            150.0,
+            [
+                bench_record("abc1234", "2024-01-01", "300"),
+                bench_record("bcd2345", "2024-01-02", "200"),
+                bench_record("cde3456", "2024-01-03", "100"),
+            ],
+            "target reached 2024-01-03",
+        ),
+        (
+            vec!["--format", "svg", "--metric", "throughput"],
+            1e9,
+            [
+                with_throughput("abc1234", "2024-01-01", "2000"),
+                with_throughput("bcd2345", "2024-01-02", "1000"),
+                with_throughput("cde3456", "2024-01-03", "500"),
+            ],
+            "target reached 2024-01-02",
+        ),
+    ] {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+        fs::write(
+            dir.path().join("ci-workflows.toml"),
+            format!("[chart.targets]\n\"Fibonacci-num=10\" = {target:?}\n"),
+        )
+        .unwrap();
+
+        plot_data(dir.path(), &args, None);
+        let svg = fs::read_to_string(dir.path().join("Fibonacci-num=10.svg")).unwrap();
+        let texts = svg_texts(&svg);
+        assert!(
+            texts.iter().any(|text| text.starts_with(reached)),
+            "{texts:?}"
+        );
+    }
+}
+
+#[test]
+fn shared_legend_ignores_empty_groups() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("plot-data.json"),
+        r#"{"version":1,"plots":{"Empty":{"lines":{}}}}"#,
+    )
+    .unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "10"),
+        bench_record("abc1234", "2024-01-01", "20").replace("Fibonacci", "Lucas"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let args = [
+        "--format",
+        "svg",
+        "--combined",
+        "all.svg",
+        "--shared-legend",
+    ];
+    plot_data(dir.path(), &args, Some("fib"));
+    let svg = fs::read_to_string(dir.path().join("all.svg")).unwrap();
+    // The empty group isn't drawn, so its lack of params doesn't stop the two charts sharing one
+    // legend entry for `rc=100`
+    let texts = svg_texts(&svg);
+    assert_eq!(
+        texts.iter().filter(|text| **text == "rc=100").count(),
+        1,
+        "{texts:?}"
+    );
+}
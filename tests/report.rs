@@ -0,0 +1,409 @@
+mod support;
+
+use std::fs;
+
+use serde_json::{json, Value};
+use support::{bench_record, run_plotter};
+
+#[test]
+fn regression_is_reported_and_fails_by_default() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "100"),
+        bench_record("bcd2345", "2024-01-02", "110"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let output = run_plotter(dir.path(), &[], None);
+    assert_eq!(output.status.code(), Some(1));
+
+    let report = fs::read_to_string(dir.path().join("regressions.json")).unwrap();
+    let report: Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report.as_array().unwrap().len(), 1);
+    assert_eq!(report[0]["group"], json!("Fibonacci-num=10"));
+    assert_eq!(report[0]["params"], json!("rc=100"));
+    assert_eq!(report[0]["old"], json!(100.0));
+    assert_eq!(report[0]["new"], json!(110.0));
+    let delta = report[0]["percent_delta"].as_f64().unwrap();
+    assert!((delta - 10.0).abs() < 1e-9, "percent_delta {delta}");
+
+    // Opting out only reports it, unless a later flag opts back in
+    let output = run_plotter(dir.path(), &["--force", "--no-fail-on-regression"], None);
+    assert!(output.status.success());
+    let args = ["--force", "--no-fail-on-regression", "--fail-on-regression"];
+    let output = run_plotter(dir.path(), &args, None);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn disjoint_intervals_still_need_the_threshold() {
+    let with_interval = |sha, date, estimate: f64| {
+        let estimate = format!(
+            r#"{estimate},"lower_bound":{},"upper_bound":{}"#,
+            estimate - 0.1,
+            estimate + 0.1
+        );
+        bench_record(sha, date, &estimate)
+    };
+    // Both steps' intervals are disjoint, but only the second exceeds the default 5% threshold
+    for (latest, regressions) in [(101.0, 0), (110.0, 1)] {
+        let dir = tempfile::tempdir().unwrap();
+        let records = [
+            with_interval("abc1234", "2024-01-01", 100.0),
+            with_interval("bcd2345", "2024-01-02", latest),
+        ];
+        fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+        run_plotter(dir.path(), &[], None);
+        let report = fs::read_to_string(dir.path().join("regressions.json")).unwrap();
+        let report: Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(report.as_array().unwrap().len(), regressions, "{latest}");
+    }
+}
+
+#[test]
+fn throughput_drop_is_a_regression() {
+    let dir = tempfile::tempdir().unwrap();
+    let with_throughput = |sha, date, estimate| {
+        bench_record(sha, date, estimate).replace(
+            r#","typical""#,
+            r#","throughput":[{"per_iteration":1000,"unit":"bytes"}],"typical""#,
+        )
+    };
+    let records = [
+        with_throughput("abc1234", "2024-01-01", "1000"),
+        with_throughput("bcd2345", "2024-01-02", "1250"),
+        bench_record("cde3456", "2024-01-03", "500"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let output = run_plotter(dir.path(), &["--metric", "throughput"], None);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("skipped 1 results without throughput"),
+        "{stderr}"
+    );
+
+    // 1000 bytes in 1000 ns, then in 1250 ns, is a 20% drop in bytes per second
+    let plots = support::read_plot_data(dir.path());
+    assert_eq!(support::line_values(&plots), [1e9, 8e8]);
+    let report = fs::read_to_string(dir.path().join("regressions.json")).unwrap();
+    let report: Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report.as_array().unwrap().len(), 1);
+    let delta = report[0]["percent_delta"].as_f64().unwrap();
+    assert!((delta + 20.0).abs() < 1e-9, "percent_delta {delta}");
+}
+
+#[test]
+fn csv_export_is_sorted() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("bcd2345", "2024-01-02", "20"),
+        bench_record("abc1234", "2024-01-01", "10"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    run_plotter(dir.path(), &["--export-csv", "out.csv"], None);
+    assert_eq!(
+        fs::read_to_string(dir.path().join("out.csv")).unwrap(),
+        "group,params,commit_date,time_ns\n\
+         Fibonacci-num=10,rc=100,2024-01-01T00:00:00+00:00,10\n\
+         Fibonacci-num=10,rc=100,2024-01-02T00:00:00+00:00,20\n"
+    );
+}
+
+#[test]
+fn markdown_table_handles_single_point() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("fib.json"),
+        bench_record("abc1234", "2024-01-01", "1500"),
+    )
+    .unwrap();
+
+    run_plotter(dir.path(), &["--export-markdown", "out.md"], None);
+    let table = fs::read_to_string(dir.path().join("out.md")).unwrap();
+    assert_eq!(
+        table.lines().last(),
+        Some("| Fibonacci-num=10 | rc=100 | — | 1.50 µs | — |")
+    );
+}
+
+// Serves canned JSON responses on a local port, e.g. for the GitHub API, recording each request's
+// method, path and body. `GET` requests are answered with `listing`
+#[cfg(feature = "http")]
+fn mock_server(listing: &'static str) -> (String, std::sync::mpsc::Receiver<(String, String)>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = BufReader::new(stream.unwrap());
+            // Connections are kept alive, so read requests until the client hangs up
+            loop {
+                let mut request_line = String::new();
+                if stream.read_line(&mut request_line).unwrap_or(0) == 0 {
+                    break;
+                }
+                let mut length = 0;
+                loop {
+                    let mut header = String::new();
+                    stream.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = header.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                stream.read_exact(&mut body).unwrap();
+                let request = request_line
+                    .split(' ')
+                    .take(2)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let _ = sender.send((request.clone(), String::from_utf8(body).unwrap()));
+                let response = if request.starts_with("GET") {
+                    listing
+                } else {
+                    r#"{"html_url":"https://github.com/o/r/pull/7#issuecomment-1"}"#
+                };
+                write!(
+                    stream.get_mut(),
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        }
+    });
+    (url, receiver)
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn github_comment_is_upserted() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("fib.json"),
+        bench_record("abc1234", "2024-01-01", "100"),
+    )
+    .unwrap();
+    let comment = |api_url: &str| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_benchmark-plotter"))
+            .args([
+                "--github-comment",
+                "--github-repo",
+                "o/r",
+                "--github-pr",
+                "7",
+            ])
+            .args(["--force"])
+            .current_dir(dir.path())
+            .env_remove("LURK_BENCH_FILES")
+            .env("GITHUB_TOKEN", "secret")
+            .env("GITHUB_API_URL", api_url)
+            .output()
+            .unwrap()
+    };
+
+    // An earlier comment with the marker is edited
+    let (url, requests) = mock_server(
+        r#"[{"id":1,"body":"LGTM"},{"id":99,"body":"<!-- benchmark-plotter -->\nold table"}]"#,
+    );
+    assert!(comment(&url).status.success());
+    let requests = requests.try_iter().collect::<Vec<_>>();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0]
+        .0
+        .starts_with("GET /repos/o/r/issues/7/comments"));
+    assert_eq!(requests[1].0, "PATCH /repos/o/r/issues/comments/99");
+    let body: Value = serde_json::from_str(&requests[1].1).unwrap();
+    let body = body["body"].as_str().unwrap();
+    assert!(body.starts_with("<!-- benchmark-plotter -->\n| Benchmark |"));
+    assert!(body.contains("| Fibonacci-num=10 | rc=100 |"));
+
+    // Otherwise a new one is posted
+    let (url, requests) = mock_server("[]");
+    assert!(comment(&url).status.success());
+    let requests = requests.try_iter().collect::<Vec<_>>();
+    assert_eq!(requests[1].0, "POST /repos/o/r/issues/7/comments");
+
+    // A failed post doesn't stop the charts from being saved
+    fs::remove_file(dir.path().join("Fibonacci-num=10.png")).unwrap();
+    let output = comment("http://127.0.0.1:1");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("failed to comment on o/r #7"));
+    assert!(dir.path().join("Fibonacci-num=10.png").exists());
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn slack_is_notified_of_regressions() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("fib.json"),
+        bench_record("abc1234", "2024-01-01", "100"),
+    )
+    .unwrap();
+    let (url, requests) = mock_server("[]");
+    let webhook = format!("{url}/hook");
+    let args = [
+        "--slack-webhook",
+        &webhook,
+        "--plot-url",
+        "https://example.com/bench/",
+        "--force",
+    ];
+
+    // Nothing is sent without regressions
+    assert!(run_plotter(dir.path(), &args, None).status.success());
+    assert_eq!(requests.try_iter().count(), 0);
+
+    fs::write(
+        dir.path().join("fib.json"),
+        bench_record("bcd2345", "2024-01-02", "110"),
+    )
+    .unwrap();
+    // The run still fails on the regression, after notifying
+    let output = run_plotter(dir.path(), &args, Some("fib"));
+    assert_eq!(output.status.code(), Some(1));
+    let requests = requests.try_iter().collect::<Vec<_>>();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].0, "POST /hook");
+    let body: Value = serde_json::from_str(&requests[0].1).unwrap();
+    let text = body["text"].as_str().unwrap();
+    assert!(text.starts_with(":warning: 1 benchmark regression"));
+    assert!(text.contains(
+        "<https://example.com/bench/Fibonacci-num=10.png|Fibonacci-num=10> `rc=100`: +10.0%"
+    ));
+}
+
+#[test]
+fn prometheus_export_has_latest_values() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "100"),
+        bench_record("bcd2345", "2024-01-02", "110"),
+        r#"{"id":"Poseidon/abc1234-2024-01-01T00_00_00+00_00/label=\"a\\b\"","typical":{"estimate":2,"unit":"us"}}"#
+            .to_owned(),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let output = run_plotter(
+        dir.path(),
+        &["--prometheus", "metrics.prom", "--no-fail-on-regression"],
+        None,
+    );
+    assert!(output.status.success());
+    let metrics = fs::read_to_string(dir.path().join("metrics.prom")).unwrap();
+    assert_eq!(
+        metrics,
+        "# TYPE benchmark_time_ns gauge\n\
+         benchmark_time_ns{group=\"Fibonacci-num=10\",params=\"rc=100\"} 110\n\
+         benchmark_time_ns{group=\"Poseidon\",params=\"label=\\\"a\\\\b\\\"\"} 2000\n"
+    );
+}
+
+#[test]
+fn influx_export_has_every_point() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "100"),
+        bench_record("bcd2345", "2024-01-02", "110"),
+        r#"{"id":"Poseidon/abc1234-2024-01-01T00_00_00+00_00/label=a b,c","typical":{"estimate":2,"unit":"us"}}"#
+            .to_owned(),
+        r#"{"id":"Short/abc1234-2024-01-01T00_00_00+00_00","typical":{"estimate":5}}"#.to_owned(),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let output = run_plotter(
+        dir.path(),
+        &["--influx", "points.lp", "--no-fail-on-regression"],
+        None,
+    );
+    assert!(output.status.success());
+    let points = fs::read_to_string(dir.path().join("points.lp")).unwrap();
+    assert_eq!(
+        points,
+        "benchmark,group=Fibonacci-num\\=10,params=rc\\=100 time_ns=100 1704067200000000000\n\
+         benchmark,group=Fibonacci-num\\=10,params=rc\\=100 time_ns=110 1704153600000000000\n\
+         benchmark,group=Poseidon,params=label\\=a\\ b\\,c time_ns=2000 1704067200000000000\n\
+         benchmark,group=Short time_ns=5 1704067200000000000\n"
+    );
+
+    // Posting needs the `http` feature
+    let output = run_plotter(
+        dir.path(),
+        &[
+            "--influx",
+            "http://localhost:8086/api/v2/write",
+            "--no-fail-on-regression",
+        ],
+        None,
+    );
+    assert_eq!(output.status.success(), cfg!(feature = "http"));
+}
+
+#[test]
+fn badge_shows_latest_value_and_trend() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "1000"),
+        bench_record("bcd2345", "2024-01-02", "2500"),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let args = [
+        "--badge",
+        "Fibonacci-num=10/rc=100",
+        "--badge-out",
+        "badge.svg",
+        "--no-fail-on-regression",
+    ];
+    let output = run_plotter(dir.path(), &args, None);
+    assert!(output.status.success());
+    let svg = fs::read_to_string(dir.path().join("badge.svg")).unwrap();
+    assert!(svg.contains("<title>Fibonacci-num=10 rc=100: 2.50 µs</title>"));
+    // Regressed, so red
+    assert!(svg.contains(r##"fill="#e05d44""##));
+
+    let args = [
+        "--badge",
+        "Fibonacci-num=10/rc=999",
+        "--badge-out",
+        "badge.svg",
+    ];
+    let output = run_plotter(dir.path(), &args, None);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn html_report_embeds_points() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = [
+        bench_record("abc1234", "2024-01-01", "1500"),
+        r#"{"id":"Tricky/bcd2345-2024-01-02T00_00_00+00_00/x=</script>","typical":{"estimate":3}}"#
+            .to_owned(),
+    ];
+    fs::write(dir.path().join("fib.json"), records.join("\n")).unwrap();
+
+    let output = run_plotter(dir.path(), &["--html", "report.html"], None);
+    assert!(output.status.success());
+    let html = fs::read_to_string(dir.path().join("report.html")).unwrap();
+    let start = html.find(r#"id="plot-data">"#).unwrap() + r#"id="plot-data">"#.len();
+    let end = start + html[start..].find("</script>").unwrap();
+    let groups: Value = serde_json::from_str(&html[start..end]).unwrap();
+    assert_eq!(groups[0]["name"], json!("Fibonacci-num=10"));
+    let point = &groups[0]["lines"][0]["points"][0];
+    assert_eq!(point["sha"], json!("abc1234"));
+    assert_eq!(point["value"], json!("1.50 µs"));
+    assert_eq!(point["date"], json!("2024-01-01 00:00:00 UTC"));
+    assert_eq!(groups[1]["lines"][0]["params"], json!("x=</script>"));
+}
@@ -0,0 +1,142 @@
+// Helpers shared by the integration tests. Not every test file uses every helper
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+
+use serde_json::Value;
+
+// Runs the plotter binary in `dir`, adding only `bench_files` if given (as `LURK_BENCH_FILES`)
+pub fn run_plotter(dir: &Path, args: &[&str], bench_files: Option<&str>) -> Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_benchmark-plotter"));
+    cmd.args(args)
+        .current_dir(dir)
+        .env_remove("LURK_BENCH_FILES");
+    if let Some(files) = bench_files {
+        cmd.env("LURK_BENCH_FILES", files);
+    }
+    cmd.output().expect("Failed to run benchmark-plotter")
+}
+
+// Like `run_plotter`, but asserts success and returns the resulting `plot-data.json`
+pub fn plot_data(dir: &Path, args: &[&str], bench_files: Option<&str>) -> Value {
+    let output = run_plotter(dir, args, bench_files);
+    assert!(
+        output.status.success(),
+        "benchmark-plotter failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    read_plot_data(dir)
+}
+
+// The plots stored in `dir`'s `plot-data.json`, without the envelope giving its version
+pub fn read_plot_data(dir: &Path) -> Value {
+    let plot_data = fs::read_to_string(dir.join("plot-data.json")).unwrap();
+    let mut plot_data: Value = serde_json::from_str(&plot_data).unwrap();
+    plot_data["plots"].take()
+}
+
+// A single Criterion record for `Fibonacci-num=10` with params `rc=100`
+pub fn bench_record(sha: &str, date: &str, estimate: &str) -> String {
+    format!(
+        r#"{{"id":"Fibonacci-num=10/{sha}-{date}T00_00_00+00_00/rc=100","typical":{{"estimate":{estimate}}}}}"#
+    )
+}
+
+// Values of the `Fibonacci-num=10` `rc=100` line in stored plot data
+pub fn line_values(plots: &Value) -> Vec<f64> {
+    plots["Fibonacci-num=10"]["lines"]["rc=100"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["y"].as_f64().unwrap())
+        .collect()
+}
+
+// Generator of reproducible Criterion output, for tests and manual QA without real benchmark runs
+//
+// Group `g` is named `Synthetic-group=<g>` and params `p` are `n=<100 * (p + 1)>`. Commit `c` is
+// dated `c` days after 2024-01-01, with a SHA derived from the seed and its index. Each value is a
+// per-params base, scaled by `trend` per commit (e.g. `1.01` for a 1% slowdown per commit) and by
+// uniform noise of up to `noise` either way (e.g. `0.05` for ±5%)
+#[derive(Debug, Clone)]
+pub struct SyntheticHistory {
+    pub groups: usize,
+    pub params: usize,
+    pub commits: usize,
+    pub seed: u64,
+    pub trend: f64,
+    pub noise: f64,
+}
+
+impl SyntheticHistory {
+    pub fn new(groups: usize, params: usize, commits: usize, seed: u64) -> Self {
+        Self {
+            groups,
+            params,
+            commits,
+            seed,
+            trend: 1.0,
+            noise: 0.05,
+        }
+    }
+
+    pub fn sha(&self, commit: usize) -> String {
+        let mut rng = SplitMix64(self.seed ^ (commit as u64).wrapping_mul(0x9e3779b97f4a7c15));
+        format!("{:07x}", rng.next() >> 36)
+    }
+
+    pub fn date(&self, commit: usize) -> String {
+        let date = chrono::NaiveDate::from_ymd(2024, 1, 1) + chrono::Duration::days(commit as i64);
+        date.format("%Y-%m-%d").to_string()
+    }
+
+    // Criterion `--message-format=json` lines, one record per group, params and commit
+    pub fn to_json(&self) -> String {
+        let mut rng = SplitMix64(self.seed);
+        let mut lines = vec![];
+        for c in 0..self.commits {
+            let (sha, date) = (self.sha(c), self.date(c));
+            for g in 0..self.groups {
+                for p in 0..self.params {
+                    let base = 1_000_000.0 * (g + 1) as f64 * (p + 1) as f64;
+                    let noise = 1.0 + self.noise * (2.0 * rng.next_f64() - 1.0);
+                    let estimate = base * self.trend.powi(c as i32) * noise;
+                    lines.push(format!(
+                        r#"{{"reason":"benchmark-complete","id":"Synthetic-group={g}/{sha}-{date}T00_00_00+00_00/n={}","typical":{{"estimate":{estimate},"lower_bound":{},"upper_bound":{},"unit":"ns"}}}}"#,
+                        100 * (p + 1),
+                        estimate * 0.98,
+                        estimate * 1.02,
+                    ));
+                }
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+// Shorthand for `SyntheticHistory::new(...).to_json()` with no trend and ±5% noise
+pub fn synthetic_history(groups: usize, params: usize, commits: usize, seed: u64) -> String {
+    SyntheticHistory::new(groups, params, commits, seed).to_json()
+}
+
+// Small deterministic PRNG, so fixtures don't depend on an external crate's output staying stable
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}